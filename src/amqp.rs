@@ -0,0 +1,123 @@
+use std::error::Error;
+use cloudevents::{AttributesReader, Event};
+use lapin::options::{BasicPublishOptions, ConfirmSelectOptions};
+use lapin::types::{AMQPValue, FieldTable, LongString, ShortString};
+use lapin::{BasicProperties, Connection, ConnectionProperties};
+use crate::binding::{self, STRUCTURED_CONTENT_TYPE};
+use crate::broker::{self, Target};
+use crate::endpoint::{ContentMode, Endpoint};
+use crate::template;
+
+/// Query parameters used by `cdevent`, which are removed before the URI is given to the AMQP client.
+const PARAMS: [&str; 2] = ["exchange", "routing_key"];
+
+/// Publishes the events to an `amqp://` (or `amqps://`) endpoint following the
+/// [AMQP protocol binding](https://github.com/cloudevents/spec/blob/v1.0.2/cloudevents/bindings/amqp-protocol-binding.md),
+/// waiting for the broker to confirm each event.
+///
+/// The `exchange` parameter selects the exchange, the default exchange if it is not given, and `routing_key`
+/// is an attribute name or template that defaults to the CDEvent type, eg. `dev.cdevents.service.deployed.0.1.1`.
+pub async fn send(endpoint: &Endpoint, events: &[Event]) -> Result<String, Box<dyn Error + Send + Sync + 'static>> {
+    let target = endpoint.target();
+    let exchange = target.param("exchange").unwrap_or("");
+    let params: Vec<String> = target.params.iter()
+        .filter(|(name, _)| !PARAMS.contains(&name.as_str()))
+        .map(|(name, value)| format!("{name}={value}"))
+        .collect();
+    let mut uri = format!("{}://{}/{}", endpoint.scheme(), target.host, target.path);
+    if !params.is_empty() {
+        uri = format!("{uri}?{}", params.join("&"));
+    }
+
+    let connection = Connection::connect(&uri, ConnectionProperties::default()).await?;
+    // The connection, and its channel, is closed whether or not the events were published.
+    let published = publish(&connection, &target, exchange, endpoint.content_mode, events).await;
+    let closed = connection.close(200, "OK").await;
+    published?;
+    closed?;
+    Ok(String::from("confirmed"))
+}
+
+/// Publishes the events on a new channel of the connection, one at a time.
+async fn publish(connection: &Connection, target: &Target, exchange: &str, content_mode: ContentMode, events: &[Event]) -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
+    let channel = connection.create_channel().await?;
+    channel.confirm_select(ConfirmSelectOptions::default()).await?;
+    for event in events {
+        let routing_key = match target.param("routing_key") {
+            Some(routing_key) => template::render_field(routing_key, event)?,
+            None => event.ty().to_string(),
+        };
+        let (properties, payload) = message(content_mode, event)?;
+        let confirmation = channel
+            .basic_publish(exchange, &routing_key, BasicPublishOptions::default(), &payload, properties)
+            .await?
+            .await?;
+        if confirmation.is_nack() {
+            return Err(format!("broker did not accept event {}", event.id()).into());
+        }
+    }
+    Ok(())
+}
+
+/// Properties and body of the AMQP message for the event.
+fn message(content_mode: ContentMode, event: &Event) -> Result<(BasicProperties, Vec<u8>), Box<dyn Error + Send + Sync + 'static>> {
+    match content_mode {
+        ContentMode::Binary => {
+            let mut headers = FieldTable::default();
+            for (name, value) in broker::attributes(event) {
+                headers.insert(ShortString::from(format!("cloudEvents_{name}")), AMQPValue::LongString(LongString::from(value)));
+            }
+            let mut properties = BasicProperties::default().with_headers(headers);
+            if let Some(content_type) = broker::content_type(event) {
+                properties = properties.with_content_type(ShortString::from(content_type));
+            }
+            Ok((properties, broker::data(event)?))
+        }
+        ContentMode::Structured => {
+            let properties = BasicProperties::default().with_content_type(ShortString::from(STRUCTURED_CONTENT_TYPE));
+            Ok((properties, binding::structured(event)?))
+        }
+        ContentMode::Batch => Err("batch content mode is not supported by the AMQP binding".into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binding::test_endpoint as endpoint;
+    use crate::binding::test_event as event;
+
+    #[test]
+    fn message_when_binary_then_cloudevents_application_properties(){
+        let (properties, body) = message(ContentMode::Binary, &event("1")).unwrap();
+
+        let headers = properties.headers().as_ref().unwrap();
+        assert_eq!(
+            headers.inner().get("cloudEvents_id"),
+            Some(&AMQPValue::LongString(LongString::from("1")))
+        );
+        assert_eq!(properties.content_type().as_ref().unwrap().as_str(), "application/json");
+        assert_eq!(body, br#"{"subject":{"id":"xyz"}}"#.to_vec());
+    }
+
+    #[test]
+    fn message_when_structured_then_cloudevent_body(){
+        let (properties, body) = message(ContentMode::Structured, &event("1")).unwrap();
+
+        assert_eq!(properties.content_type().as_ref().unwrap().as_str(), STRUCTURED_CONTENT_TYPE);
+        assert_eq!(body, serde_json::to_vec(&event("1")).unwrap());
+    }
+
+    /// Needs a local broker, eg. `docker run -p 5672:5672 rabbitmq`,
+    /// and is run with `cargo test --features amqp -- --ignored`.
+    #[tokio::test]
+    #[ignore]
+    async fn send_when_local_broker_then_confirmed(){
+        let host = std::env::var("CDEVENT_TEST_AMQP").unwrap_or_else(|_| String::from("guest:guest@localhost:5672"));
+        let endpoint = endpoint(format!("amqp://{host}/%2f?exchange=amq.topic"), ContentMode::Binary);
+
+        let confirmed = send(&endpoint, &[event("1")]).await;
+
+        assert!(confirmed.is_ok(), "{confirmed:?}");
+    }
+}
@@ -8,13 +8,45 @@ pub fn parse_key_val(s: &str) -> Result<(String, String), Box<dyn Error + Send +
     Ok((s[..pos].parse()?, s[pos + 1..].parse()?))
 }
 
+/// Parses `KEY=value` pairs separated by commas.
 pub fn parse_comma_delimited(s: &str, error_message: &str) -> Result<Vec<(String, String)>, Box<dyn Error + Send + Sync + 'static>>
 {
     if s.is_empty() {
         return Err(error_message.into());
     }
-    let r = s.split(',').map(|kv| parse_key_val(kv).unwrap()).collect();
-    Ok(r)
+    s.split(',').map(parse_key_val).collect()
+}
+
+/// Parses `KEY=value` pairs separated by commas, where values containing commas
+/// can be wrapped in double quotes or have the comma escaped with `\`.
+pub fn parse_quoted_comma_delimited(s: &str, error_message: &str) -> Result<Vec<(String, String)>, Box<dyn Error + Send + Sync + 'static>>
+{
+    if s.is_empty() {
+        return Err(error_message.into());
+    }
+    split_unquoted(s, ',')?.iter().map(|kv| parse_key_val(kv)).collect()
+}
+
+/// Splits on `delimiter` except where it is quoted or escaped, removing the quotes and escapes.
+fn split_unquoted(s: &str, delimiter: char) -> Result<Vec<String>, Box<dyn Error + Send + Sync + 'static>>
+{
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => current.push(chars.next().ok_or_else(|| format!("trailing `\\` in `{s}`"))?),
+            '"' => quoted = !quoted,
+            c if c == delimiter && !quoted => parts.push(std::mem::take(&mut current)),
+            c => current.push(c),
+        }
+    }
+    if quoted {
+        return Err(format!("unterminated quote in `{s}`").into());
+    }
+    parts.push(current);
+    Ok(parts)
 }
 
 #[cfg(test)]
@@ -50,4 +82,34 @@ mod tests {
         let result: Result<Vec<(String, String)>, Box<dyn Error+Send+Sync>> = parse_comma_delimited("key1=value1,key2=value2", "error");
         assert_eq!(result.unwrap(), vec![(String::from("key1"), String::from("value1")), (String::from("key2"), String::from("value2"))])
     }
+
+    #[test]
+    fn parse_quoted_when_quoted_value_with_comma_then_single_value(){
+        let result: Result<Vec<(String, String)>, Box<dyn Error+Send+Sync>> = parse_quoted_comma_delimited("key1=\"a,b\",key2=c", "error");
+        assert_eq!(result.unwrap(), vec![(String::from("key1"), String::from("a,b")), (String::from("key2"), String::from("c"))])
+    }
+
+    #[test]
+    fn parse_quoted_when_escaped_comma_then_single_value(){
+        let result: Result<Vec<(String, String)>, Box<dyn Error+Send+Sync>> = parse_quoted_comma_delimited("key1=a\\,b", "error");
+        assert_eq!(result.unwrap(), vec![(String::from("key1"), String::from("a,b"))])
+    }
+
+    #[test]
+    fn parse_custom_data_when_quotes_and_backslashes_then_kept(){
+        let result: Result<Vec<(String, String)>, Box<dyn Error+Send+Sync>> = parse_comma_delimited("path=C:\\temp,title=\"v1\"", "error");
+        assert_eq!(result.unwrap(), vec![(String::from("path"), String::from("C:\\temp")), (String::from("title"), String::from("\"v1\""))])
+    }
+
+    #[test]
+    fn parse_custom_data_when_missing_equal_sign_then_error(){
+        let result: Result<Vec<(String, String)>, Box<dyn Error+Send+Sync>> = parse_comma_delimited("key1=value1,key2", "error");
+        assert!(result.is_err())
+    }
+
+    #[test]
+    fn parse_quoted_when_unterminated_quote_then_error(){
+        let result: Result<Vec<(String, String)>, Box<dyn Error+Send+Sync>> = parse_quoted_comma_delimited("key1=\"value1", "error");
+        assert!(result.is_err())
+    }
 }
@@ -0,0 +1,39 @@
+use std::error::Error;
+use cloudevents::Event;
+
+/// Content type of a CloudEvent serialized in structured content mode.
+pub const STRUCTURED_CONTENT_TYPE: &str = "application/cloudevents+json; charset=UTF-8";
+/// Content type of a JSON array of CloudEvents serialized in batch content mode.
+pub const BATCH_CONTENT_TYPE: &str = "application/cloudevents-batch+json; charset=UTF-8";
+
+/// The whole event, which is the message body in structured content mode.
+pub fn structured(event: &Event) -> Result<Vec<u8>, Box<dyn Error + Send + Sync + 'static>> {
+    Ok(serde_json::to_vec(event)?)
+}
+
+/// The event the tests of the endpoints and bindings send.
+#[cfg(test)]
+pub fn test_event(id: &str) -> Event {
+    use cloudevents::{EventBuilder, EventBuilderV10};
+    EventBuilderV10::new()
+        .id(id)
+        .source("zsh")
+        .ty("dev.cdevents.service.deployed.0.1.1")
+        .subject("xyz")
+        .data("application/json", serde_json::json!({"subject": {"id": "xyz"}}))
+        .build()
+        .unwrap()
+}
+
+/// An endpoint with only a URL and content mode, for the tests of the transports.
+#[cfg(test)]
+pub fn test_endpoint(url: impl Into<String>, content_mode: crate::endpoint::ContentMode) -> crate::endpoint::Endpoint {
+    crate::endpoint::Endpoint {
+        url: url.into(),
+        headers: Vec::new(),
+        oauth2: None,
+        hmac: None,
+        content_mode,
+        compress: None,
+    }
+}
@@ -0,0 +1,101 @@
+use std::error::Error;
+use cloudevents::{AttributesReader, Data, Event};
+use crate::endpoint::Endpoint;
+
+// ========= Broker Bindings =========
+// Shared by the Kafka, NATS, AMQP and MQTT endpoints, and only compiled with one of their features.
+
+impl Endpoint {
+    /// Splits a broker style URL, eg. `kafka://broker:9092/topic?key=subject`, into its parts.
+    pub fn target(&self) -> Target {
+        let rest = self.url.split_once("://").map(|(_, rest)| rest).unwrap_or(&self.url);
+        let (rest, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let (host, path) = rest.split_once('/').unwrap_or((rest, ""));
+        let params = query.split('&')
+            .filter(|param| !param.is_empty())
+            .map(|param| {
+                let (name, value) = param.split_once('=').unwrap_or((param, ""));
+                (name.to_string(), value.to_string())
+            })
+            .collect();
+        Target {
+            host: host.to_string(),
+            path: path.to_string(),
+            params
+        }
+    }
+}
+
+/// The parts of a broker style endpoint URL.
+#[derive(Debug, PartialEq)]
+pub struct Target {
+    pub host: String,
+    pub path: String,
+    pub params: Vec<(String, String)>,
+}
+
+impl Target {
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params.iter().find(|(param, _)| param == name).map(|(_, value)| value.as_str())
+    }
+}
+
+/// The event's attributes and extensions as name/value strings for binary content mode bindings.
+///
+/// `datacontenttype` is left out as every protocol binding carries it as the message's own content type.
+pub fn attributes(event: &Event) -> Vec<(String, String)> {
+    event.iter()
+        .filter(|(name, _)| *name != "datacontenttype")
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+pub fn content_type(event: &Event) -> Option<&str> {
+    event.datacontenttype()
+}
+
+/// The event data, which is the message body in binary content mode.
+pub fn data(event: &Event) -> Result<Vec<u8>, Box<dyn Error + Send + Sync + 'static>> {
+    let data = match event.data() {
+        Some(Data::Json(json)) => serde_json::to_vec(json)?,
+        Some(Data::String(s)) => s.clone().into_bytes(),
+        Some(Data::Binary(bytes)) => bytes.clone(),
+        None => Vec::new(),
+    };
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binding::test_endpoint as endpoint;
+    use crate::binding::test_event as event;
+    use crate::endpoint::ContentMode;
+
+    #[test]
+    fn attributes_when_event_then_attributes_without_content_type(){
+        let attributes = attributes(&event("1"));
+        assert!(attributes.contains(&(String::from("id"), String::from("1"))));
+        assert!(attributes.contains(&(String::from("subject"), String::from("xyz"))));
+        assert!(!attributes.iter().any(|(name, _)| name == "datacontenttype"));
+    }
+
+    #[test]
+    fn data_when_json_then_serialized_json(){
+        assert_eq!(data(&event("1")).unwrap(), br#"{"subject":{"id":"xyz"}}"#.to_vec())
+    }
+
+    #[test]
+    fn target_when_broker_url_then_parts(){
+        let endpoint = endpoint(String::from("kafka://b1:9092,b2:9092/deployments?key=subject&acks=all"), ContentMode::Binary);
+
+        let target = endpoint.target();
+
+        assert_eq!(endpoint.scheme(), "kafka");
+        assert_eq!(target.host, "b1:9092,b2:9092");
+        assert_eq!(target.path, "deployments");
+        assert_eq!(target.param("key"), Some("subject"));
+        assert_eq!(target.param("acks"), Some("all"));
+        assert_eq!(target.param("nope"), None);
+    }
+}
@@ -0,0 +1,215 @@
+use std::error::Error;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use cdevents_sdk::CDEvent;
+use clap::{arg, Arg, ArgMatches};
+use cloudevents::{AttributesReader, Event};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+// ========= Chains and Links =========
+/// The chain an event belongs to, and the events it is linked to, as in CDEvents 0.4.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ChainArgs {
+    pub chain_id: Option<String>,
+    /// The IDs of the events this one follows, or relates to with `relation`.
+    pub parents: Vec<String>,
+    /// The kind of a `RELATION` link to the parents, such as `TRIGGER`. Without it they are `PATH` links.
+    pub relation: Option<String>,
+    /// A `KEY=value` file, such as `$GITHUB_ENV`, the chain is handed off to for later steps.
+    pub env_file: Option<PathBuf>,
+}
+
+impl ChainArgs {
+    fn is_empty(&self) -> bool {
+        self.chain_id.is_none() && self.parents.is_empty()
+    }
+
+    /// The chain of an event following `event_id`, for the next event a command sends.
+    pub fn followed_by(&self, event_id: &str) -> ChainArgs {
+        ChainArgs {
+            parents: vec![event_id.to_string()],
+            relation: None,
+            ..self.clone()
+        }
+    }
+}
+
+pub fn chain_args() -> [Arg; 4] {
+    [
+        arg!(--"chain-id" <CHAIN_ID> "The chain the event belongs to, generated when --chain-env-file is given to start a chain")
+            .env("CDEVENT_CHAIN_ID"),
+        arg!(--"link-parent" <EVENT_ID> "The ID of the event this one follows in the chain, can be repeated")
+            .env("CDEVENT_LINK_PARENT")
+            .action(clap::ArgAction::Append),
+        arg!(--"link-relation" <KIND> "Link to the parents as a relation of this kind, eg. TRIGGER, instead of following them")
+            .requires("link-parent"),
+        arg!(--"chain-env-file" <FILE> "Append CDEVENT_CHAIN_ID and CDEVENT_LINK_PARENT to this file, eg. $GITHUB_ENV, for later steps")
+            .value_parser(clap::value_parser!(PathBuf)),
+    ]
+}
+
+pub fn chain_parse(matches: &ArgMatches) -> ChainArgs {
+    let env_file = matches.get_one::<PathBuf>("chain-env-file").cloned();
+    let chain_id = matches.get_one::<String>("chain-id").cloned()
+        .or_else(|| env_file.as_ref().map(|_| Uuid::new_v4().to_string()));
+    ChainArgs {
+        chain_id,
+        parents: matches.get_many::<String>("link-parent").into_iter().flatten().cloned().collect(),
+        relation: matches.get_one::<String>("link-relation").cloned(),
+        env_file,
+    }
+}
+
+/// Builds the CloudEvent of the CDEvent in the chain, with the ID `id` gives for the chained CDEvent JSON,
+/// so the chain is part of what the hash ID strategy covers.
+pub fn to_cloud_event(cd_event: CDEvent, chain: &ChainArgs, id: impl FnOnce(&Value) -> String) -> Result<Event, Box<dyn Error + Send + Sync + 'static>> {
+    let mut document = serde_json::to_value(&cd_event)?;
+    link(&mut document, chain);
+    let id = id(&document);
+    let mut event: Event = cd_event.with_id(id.clone().try_into()?).try_into()?;
+    if !chain.is_empty() {
+        // The CDEvent type only has the 0.3 context, so the chained document replaces the data it gave.
+        document["context"]["id"] = json!(id);
+        let content_type = event.datacontenttype().unwrap_or("application/json").to_string();
+        event.set_data(content_type, document);
+    }
+    Ok(event)
+}
+
+/// Adds the `chainId` and `links` to the context of the CDEvent JSON.
+///
+/// The SDK only builds 0.3 events, whose types CDEvents 0.4 changed the versions of, so the spec version is kept
+/// and the chain fields are an extension of the 0.3 context that 0.4 aware receivers understand.
+fn link(cd_event: &mut Value, chain: &ChainArgs) {
+    if chain.is_empty() {
+        return;
+    }
+    if !cd_event["context"].is_object() {
+        cd_event["context"] = json!({});
+    }
+    let context = &mut cd_event["context"];
+    if let Some(chain_id) = &chain.chain_id {
+        context["chainId"] = json!(chain_id);
+    }
+    if !chain.parents.is_empty() {
+        context["links"] = Value::Array(chain.parents.iter().map(|parent| to_link(parent, chain)).collect());
+    }
+}
+
+/// An embedded link, see <https://github.com/cdevents/spec/blob/v0.4.0/links.md>.
+fn to_link(parent: &str, chain: &ChainArgs) -> Value {
+    let mut link = match &chain.relation {
+        Some(kind) => json!({"linkType": "RELATION", "linkKind": kind, "target": {"contextId": parent}}),
+        None => json!({"linkType": "PATH", "from": {"contextId": parent}}),
+    };
+    if let Some(chain_id) = &chain.chain_id {
+        link["chainId"] = json!(chain_id);
+    }
+    link
+}
+
+/// Appends the chain, with the event as the parent of the next one, to the `--chain-env-file`.
+pub fn handoff(chain: &ChainArgs, event: &Event) -> std::io::Result<()> {
+    let Some(path) = &chain.env_file else {
+        return Ok(());
+    };
+    write_env(path, chain.chain_id.as_deref(), event.id())
+}
+
+fn write_env(path: &Path, chain_id: Option<&str>, parent: &str) -> std::io::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    if let Some(chain_id) = chain_id {
+        writeln!(file, "CDEVENT_CHAIN_ID={chain_id}")?;
+    }
+    writeln!(file, "CDEVENT_LINK_PARENT={parent}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::id::{self, IdStrategy};
+    use crate::service::ServiceDeployedArgs;
+
+    fn cd_event() -> Value {
+        json!({"context": {"version": "0.3.0", "id": "1"}, "subject": {"id": "xyz"}})
+    }
+
+    fn deployed() -> CDEvent {
+        CDEvent::from(ServiceDeployedArgs {
+            id: None,
+            id_strategy: IdStrategy::Hash,
+            source: String::from("zsh"),
+            subject_id: String::from("xyz"),
+            env_id: String::from("local"),
+            env_name: None,
+            env_source: None,
+            artifact: Some(String::from("pkg:oci/app@sha256%3A0b31b1c02ff458ad9b7b81cbdf8f028bd54699fa151f221d1e8de6817db93427")),
+            custom_data: None,
+            timestamp: None,
+        })
+    }
+
+    fn chain(chain_id: &str) -> ChainArgs {
+        ChainArgs { chain_id: Some(String::from(chain_id)), parents: vec![String::from("0")], ..ChainArgs::default() }
+    }
+
+    #[test]
+    fn link_when_chain_and_parent_then_chain_id_path_link_and_same_version(){
+        let mut cd_event = cd_event();
+
+        link(&mut cd_event, &chain("chain-1"));
+
+        assert_eq!(cd_event["context"], json!({
+            "version": "0.3.0",
+            "id": "1",
+            "chainId": "chain-1",
+            "links": [{"chainId": "chain-1", "linkType": "PATH", "from": {"contextId": "0"}}],
+        }));
+    }
+
+    #[test]
+    fn link_when_relation_then_relation_link(){
+        let chain = ChainArgs { parents: vec![String::from("0")], relation: Some(String::from("TRIGGER")), ..ChainArgs::default() };
+        let mut cd_event = cd_event();
+
+        link(&mut cd_event, &chain);
+
+        assert_eq!(cd_event["context"]["links"], json!([{"linkType": "RELATION", "linkKind": "TRIGGER", "target": {"contextId": "0"}}]));
+    }
+
+    #[test]
+    fn link_when_no_chain_then_unchanged(){
+        let mut cd_event = cd_event();
+
+        link(&mut cd_event, &ChainArgs::default());
+
+        assert_eq!(cd_event, self::cd_event());
+    }
+
+    #[test]
+    fn to_cloud_event_when_hash_then_chain_is_part_of_the_id(){
+        let hash = |document: &Value| id::generate(IdStrategy::Hash, document, false);
+        let cd_event = deployed();
+
+        let first = to_cloud_event(cd_event.clone(), &chain("chain-1"), hash).unwrap();
+        let second = to_cloud_event(cd_event, &chain("chain-2"), hash).unwrap();
+
+        assert_ne!(first.id(), second.id());
+        let Some(cloudevents::Data::Json(data)) = first.data() else { panic!("no JSON data") };
+        assert_eq!(data["context"]["id"], first.id());
+        assert_eq!(data["context"]["chainId"], "chain-1");
+    }
+
+    #[test]
+    fn write_env_when_called_twice_then_appends_latest_parent(){
+        let path = std::env::temp_dir().join(format!("cdevent-test-{}-chain.env", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        write_env(&path, Some("chain-1"), "1").unwrap();
+        write_env(&path, Some("chain-1"), "2").unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "CDEVENT_CHAIN_ID=chain-1\nCDEVENT_LINK_PARENT=1\nCDEVENT_CHAIN_ID=chain-1\nCDEVENT_LINK_PARENT=2\n");
+    }
+}
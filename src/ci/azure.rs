@@ -0,0 +1,78 @@
+use super::{present, CiEnvironment, CiMetadata, CiProvider, Env};
+
+/// Fills events from the [Azure Pipelines predefined variables](https://learn.microsoft.com/en-us/azure/devops/pipelines/build/variables).
+///
+/// The build results page is the source, and a deployment job provides the environment of the event.
+pub struct Azure;
+
+impl CiProvider for Azure {
+    fn name(&self) -> &'static str {
+        "azure"
+    }
+
+    fn metadata(&self, env: Env) -> Option<CiMetadata> {
+        if !env("TF_BUILD")?.eq_ignore_ascii_case("true") {
+            return None;
+        }
+        let collection = env("SYSTEM_COLLECTIONURI")?;
+        let project = env("SYSTEM_TEAMPROJECT")?;
+        let build_id = env("BUILD_BUILDID")?;
+        let build_url = format!(
+            "{}/{}/_build/results?buildId={build_id}",
+            collection.trim_end_matches('/'),
+            project.replace(' ', "%20")
+        );
+        let custom_data = present(&[
+            ("ciProvider", Some(String::from("azure"))),
+            ("ciRunId", Some(build_id)),
+            ("ciRunUrl", Some(build_url.clone())),
+            ("ciWorkflow", env("BUILD_DEFINITIONNAME")),
+            ("ciJob", env("SYSTEM_JOBDISPLAYNAME")),
+            ("ciActor", env("BUILD_REQUESTEDFOR")),
+            ("ciRepository", env("BUILD_REPOSITORY_URI")),
+            ("ciCommit", env("BUILD_SOURCEVERSION")),
+            ("ciRef", env("BUILD_SOURCEBRANCH")),
+        ]);
+        let environment = env("ENVIRONMENT_NAME")
+            .filter(|name| !name.is_empty())
+            .map(|name| CiEnvironment {
+                id: name.clone(),
+                name: Some(name),
+                source: None,
+            });
+        Some(CiMetadata {
+            source: Some(build_url),
+            custom_data,
+            environment
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ci::env_of;
+
+    #[test]
+    fn metadata_when_deployment_job_then_build_url_and_environment(){
+        let env = env_of(&[
+            ("TF_BUILD", "True"),
+            ("SYSTEM_COLLECTIONURI", "https://dev.azure.com/acme/"),
+            ("SYSTEM_TEAMPROJECT", "Web Apps"),
+            ("BUILD_BUILDID", "99"),
+            ("BUILD_SOURCEVERSION", "ffac537e6cbbf934b08745a378932722df287a53"),
+            ("ENVIRONMENT_NAME", "production"),
+        ]);
+
+        let metadata = Azure.metadata(&env).unwrap();
+
+        assert_eq!(metadata.source.as_deref(), Some("https://dev.azure.com/acme/Web%20Apps/_build/results?buildId=99"));
+        assert!(metadata.custom_data.contains(&(String::from("ciCommit"), String::from("ffac537e6cbbf934b08745a378932722df287a53"))));
+        assert_eq!(metadata.environment.unwrap().id, "production");
+    }
+
+    #[test]
+    fn metadata_when_not_azure_then_none(){
+        assert_eq!(Azure.metadata(&env_of(&[("BUILD_BUILDID", "99")])), None)
+    }
+}
@@ -0,0 +1,63 @@
+use super::{present, CiMetadata, CiProvider, Env};
+
+/// Fills events from the [Buildkite environment variables](https://buildkite.com/docs/pipelines/configure/environment-variables).
+pub struct Buildkite;
+
+impl CiProvider for Buildkite {
+    fn name(&self) -> &'static str {
+        "buildkite"
+    }
+
+    fn metadata(&self, env: Env) -> Option<CiMetadata> {
+        if env("BUILDKITE").as_deref() != Some("true") {
+            return None;
+        }
+        let build_url = env("BUILDKITE_BUILD_URL")?;
+        let custom_data = present(&[
+            ("ciProvider", Some(String::from("buildkite"))),
+            ("ciRunId", env("BUILDKITE_BUILD_ID")),
+            ("ciRunUrl", Some(build_url.clone())),
+            ("ciWorkflow", env("BUILDKITE_PIPELINE_SLUG")),
+            ("ciJob", env("BUILDKITE_LABEL")),
+            ("ciJobId", env("BUILDKITE_JOB_ID")),
+            ("ciActor", env("BUILDKITE_BUILD_CREATOR")),
+            ("ciRepository", env("BUILDKITE_REPO")),
+            ("ciCommit", env("BUILDKITE_COMMIT")),
+            ("ciRef", env("BUILDKITE_BRANCH")),
+            ("ciTag", env("BUILDKITE_TAG")),
+        ]);
+        Some(CiMetadata {
+            source: Some(build_url),
+            custom_data,
+            environment: None
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ci::env_of;
+
+    #[test]
+    fn metadata_when_buildkite_then_build_url_and_custom_data(){
+        let env = env_of(&[
+            ("BUILDKITE", "true"),
+            ("BUILDKITE_BUILD_URL", "https://buildkite.com/acme/app/builds/3"),
+            ("BUILDKITE_BUILD_ID", "0190a1b2-c3d4-e5f6-0718-293a4b5c6d7e"),
+            ("BUILDKITE_PIPELINE_SLUG", "app"),
+            ("BUILDKITE_COMMIT", "ffac537e6cbbf934b08745a378932722df287a53"),
+        ]);
+
+        let metadata = Buildkite.metadata(&env).unwrap();
+
+        assert_eq!(metadata.source.as_deref(), Some("https://buildkite.com/acme/app/builds/3"));
+        assert!(metadata.custom_data.contains(&(String::from("ciWorkflow"), String::from("app"))));
+        assert!(metadata.custom_data.contains(&(String::from("ciCommit"), String::from("ffac537e6cbbf934b08745a378932722df287a53"))));
+    }
+
+    #[test]
+    fn metadata_when_not_buildkite_then_none(){
+        assert_eq!(Buildkite.metadata(&env_of(&[("BUILDKITE_BUILD_URL", "https://buildkite.com/acme/app/builds/3")])), None)
+    }
+}
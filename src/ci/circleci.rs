@@ -0,0 +1,61 @@
+use super::{present, CiMetadata, CiProvider, Env};
+
+/// Fills events from the [CircleCI built-in environment variables](https://circleci.com/docs/variables/#built-in-environment-variables).
+pub struct CircleCi;
+
+impl CiProvider for CircleCi {
+    fn name(&self) -> &'static str {
+        "circleci"
+    }
+
+    fn metadata(&self, env: Env) -> Option<CiMetadata> {
+        if env("CIRCLECI").as_deref() != Some("true") {
+            return None;
+        }
+        let job_url = env("CIRCLE_BUILD_URL")?;
+        let custom_data = present(&[
+            ("ciProvider", Some(String::from("circleci"))),
+            ("ciRunId", env("CIRCLE_WORKFLOW_ID")),
+            ("ciJob", env("CIRCLE_JOB")),
+            ("ciJobId", env("CIRCLE_BUILD_NUM")),
+            ("ciJobUrl", Some(job_url.clone())),
+            ("ciActor", env("CIRCLE_USERNAME")),
+            ("ciRepository", env("CIRCLE_REPOSITORY_URL")),
+            ("ciCommit", env("CIRCLE_SHA1")),
+            ("ciRef", env("CIRCLE_BRANCH")),
+            ("ciTag", env("CIRCLE_TAG")),
+        ]);
+        Some(CiMetadata {
+            source: Some(job_url),
+            custom_data,
+            environment: None
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ci::env_of;
+
+    #[test]
+    fn metadata_when_circleci_then_job_url_and_workflow_id(){
+        let env = env_of(&[
+            ("CIRCLECI", "true"),
+            ("CIRCLE_BUILD_URL", "https://circleci.com/gh/acme/app/7"),
+            ("CIRCLE_WORKFLOW_ID", "6b2f6c5e-1c0b-4c5e-9f7e-1a2b3c4d5e6f"),
+            ("CIRCLE_USERNAME", "octocat"),
+        ]);
+
+        let metadata = CircleCi.metadata(&env).unwrap();
+
+        assert_eq!(metadata.source.as_deref(), Some("https://circleci.com/gh/acme/app/7"));
+        assert!(metadata.custom_data.contains(&(String::from("ciRunId"), String::from("6b2f6c5e-1c0b-4c5e-9f7e-1a2b3c4d5e6f"))));
+        assert!(metadata.custom_data.contains(&(String::from("ciActor"), String::from("octocat"))));
+    }
+
+    #[test]
+    fn metadata_when_not_circleci_then_none(){
+        assert_eq!(CircleCi.metadata(&env_of(&[("CIRCLE_BUILD_URL", "https://circleci.com/gh/acme/app/7")])), None)
+    }
+}
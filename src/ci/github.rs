@@ -0,0 +1,69 @@
+use super::{present, CiMetadata, CiProvider, Env};
+
+/// Fills events from the [GitHub Actions default variables](https://docs.github.com/en/actions/writing-workflows/choosing-what-your-workflow-does/store-information-in-variables#default-environment-variables).
+pub struct GitHub;
+
+impl CiProvider for GitHub {
+    fn name(&self) -> &'static str {
+        "github"
+    }
+
+    fn metadata(&self, env: Env) -> Option<CiMetadata> {
+        if env("GITHUB_ACTIONS").as_deref() != Some("true") {
+            return None;
+        }
+        let server = env("GITHUB_SERVER_URL").unwrap_or_else(|| String::from("https://github.com"));
+        let repository = env("GITHUB_REPOSITORY")?;
+        let run_id = env("GITHUB_RUN_ID")?;
+        let run_url = format!("{server}/{repository}/actions/runs/{run_id}");
+        let custom_data = present(&[
+            ("ciProvider", Some(String::from("github"))),
+            ("ciRunId", Some(run_id)),
+            ("ciRunAttempt", env("GITHUB_RUN_ATTEMPT")),
+            ("ciRunUrl", Some(run_url.clone())),
+            ("ciWorkflow", env("GITHUB_WORKFLOW")),
+            ("ciJob", env("GITHUB_JOB")),
+            ("ciActor", env("GITHUB_ACTOR")),
+            ("ciRepository", Some(format!("{server}/{repository}"))),
+            ("ciCommit", env("GITHUB_SHA")),
+            ("ciRef", env("GITHUB_REF")),
+        ]);
+        Some(CiMetadata {
+            source: Some(run_url),
+            custom_data,
+            environment: None
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ci::env_of;
+
+    #[test]
+    fn metadata_when_github_actions_then_run_url_and_custom_data(){
+        let env = env_of(&[
+            ("GITHUB_ACTIONS", "true"),
+            ("GITHUB_SERVER_URL", "https://github.com"),
+            ("GITHUB_REPOSITORY", "acme/app"),
+            ("GITHUB_RUN_ID", "42"),
+            ("GITHUB_RUN_ATTEMPT", "1"),
+            ("GITHUB_ACTOR", "octocat"),
+            ("GITHUB_SHA", "ffac537e6cbbf934b08745a378932722df287a53"),
+            ("GITHUB_REF", "refs/heads/main"),
+        ]);
+
+        let metadata = GitHub.metadata(&env).unwrap();
+
+        assert_eq!(metadata.source.as_deref(), Some("https://github.com/acme/app/actions/runs/42"));
+        assert!(metadata.custom_data.contains(&(String::from("ciActor"), String::from("octocat"))));
+        assert!(metadata.custom_data.contains(&(String::from("ciCommit"), String::from("ffac537e6cbbf934b08745a378932722df287a53"))));
+        assert!(!metadata.custom_data.iter().any(|(name, _)| name == "ciWorkflow"));
+    }
+
+    #[test]
+    fn metadata_when_not_github_actions_then_none(){
+        assert_eq!(GitHub.metadata(&env_of(&[("GITHUB_REPOSITORY", "acme/app")])), None)
+    }
+}
@@ -0,0 +1,81 @@
+use super::{present, CiEnvironment, CiMetadata, CiProvider, Env};
+
+/// Fills events from the [GitLab CI predefined variables](https://docs.gitlab.com/ee/ci/variables/predefined_variables.html).
+///
+/// The project URL is the source, and a job with an `environment` provides the environment of the event.
+pub struct GitLab;
+
+impl CiProvider for GitLab {
+    fn name(&self) -> &'static str {
+        "gitlab"
+    }
+
+    fn metadata(&self, env: Env) -> Option<CiMetadata> {
+        if env("GITLAB_CI").as_deref() != Some("true") {
+            return None;
+        }
+        let project_url = env("CI_PROJECT_URL")?;
+        let custom_data = present(&[
+            ("ciProvider", Some(String::from("gitlab"))),
+            ("ciRunId", env("CI_PIPELINE_ID")),
+            ("ciRunUrl", env("CI_PIPELINE_URL")),
+            ("ciJob", env("CI_JOB_NAME")),
+            ("ciJobId", env("CI_JOB_ID")),
+            ("ciJobUrl", env("CI_JOB_URL")),
+            ("ciActor", env("GITLAB_USER_LOGIN")),
+            ("ciRepository", Some(project_url.clone())),
+            ("ciCommit", env("CI_COMMIT_SHA")),
+            ("ciRef", env("CI_COMMIT_REF_NAME")),
+            ("ciTag", env("CI_COMMIT_TAG")),
+        ]);
+        let environment = env("CI_ENVIRONMENT_NAME")
+            .filter(|name| !name.is_empty())
+            .map(|name| CiEnvironment {
+                id: name.clone(),
+                name: Some(name),
+                source: env("CI_ENVIRONMENT_URL").filter(|url| !url.is_empty()),
+            });
+        Some(CiMetadata {
+            source: Some(project_url),
+            custom_data,
+            environment
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ci::env_of;
+
+    #[test]
+    fn metadata_when_deploy_job_then_project_source_and_environment(){
+        let env = env_of(&[
+            ("GITLAB_CI", "true"),
+            ("CI_PROJECT_URL", "https://gitlab.com/acme/app"),
+            ("CI_PIPELINE_ID", "1000"),
+            ("CI_JOB_ID", "2000"),
+            ("CI_COMMIT_SHA", "ffac537e6cbbf934b08745a378932722df287a53"),
+            ("CI_ENVIRONMENT_NAME", "production"),
+            ("CI_ENVIRONMENT_URL", "https://app.acme.com"),
+        ]);
+
+        let metadata = GitLab.metadata(&env).unwrap();
+
+        assert_eq!(metadata.source.as_deref(), Some("https://gitlab.com/acme/app"));
+        assert!(metadata.custom_data.contains(&(String::from("ciRunId"), String::from("1000"))));
+        assert!(metadata.custom_data.contains(&(String::from("ciJobId"), String::from("2000"))));
+        assert_eq!(metadata.environment, Some(CiEnvironment {
+            id: String::from("production"),
+            name: Some(String::from("production")),
+            source: Some(String::from("https://app.acme.com")),
+        }));
+    }
+
+    #[test]
+    fn metadata_when_job_without_environment_then_no_environment(){
+        let env = env_of(&[("GITLAB_CI", "true"), ("CI_PROJECT_URL", "https://gitlab.com/acme/app")]);
+
+        assert_eq!(GitLab.metadata(&env).unwrap().environment, None)
+    }
+}
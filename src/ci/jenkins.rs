@@ -0,0 +1,61 @@
+use super::{present, CiMetadata, CiProvider, Env};
+
+/// Fills events from the [Jenkins environment variables](https://www.jenkins.io/doc/book/pipeline/jenkinsfile/#using-environment-variables),
+/// with the repository and commit set by the Git plugin.
+pub struct Jenkins;
+
+impl CiProvider for Jenkins {
+    fn name(&self) -> &'static str {
+        "jenkins"
+    }
+
+    fn metadata(&self, env: Env) -> Option<CiMetadata> {
+        env("JENKINS_URL")?;
+        let build_url = env("BUILD_URL")?;
+        let custom_data = present(&[
+            ("ciProvider", Some(String::from("jenkins"))),
+            ("ciRunId", env("BUILD_NUMBER")),
+            ("ciRunUrl", Some(build_url.clone())),
+            ("ciJob", env("JOB_NAME")),
+            ("ciActor", env("BUILD_USER_ID")),
+            ("ciRepository", env("GIT_URL")),
+            ("ciCommit", env("GIT_COMMIT")),
+            ("ciRef", env("GIT_BRANCH")),
+            ("ciTag", env("TAG_NAME")),
+        ]);
+        Some(CiMetadata {
+            source: Some(build_url),
+            custom_data,
+            environment: None
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ci::env_of;
+
+    #[test]
+    fn metadata_when_jenkins_then_build_url_and_git_data(){
+        let env = env_of(&[
+            ("JENKINS_URL", "https://jenkins.acme.com/"),
+            ("BUILD_URL", "https://jenkins.acme.com/job/app/12/"),
+            ("BUILD_NUMBER", "12"),
+            ("JOB_NAME", "app"),
+            ("GIT_URL", "https://github.com/acme/app.git"),
+            ("GIT_COMMIT", "ffac537e6cbbf934b08745a378932722df287a53"),
+        ]);
+
+        let metadata = Jenkins.metadata(&env).unwrap();
+
+        assert_eq!(metadata.source.as_deref(), Some("https://jenkins.acme.com/job/app/12/"));
+        assert!(metadata.custom_data.contains(&(String::from("ciRunId"), String::from("12"))));
+        assert!(metadata.custom_data.contains(&(String::from("ciRepository"), String::from("https://github.com/acme/app.git"))));
+    }
+
+    #[test]
+    fn metadata_when_not_jenkins_then_none(){
+        assert_eq!(Jenkins.metadata(&env_of(&[("BUILD_URL", "https://ci.acme.com/1")])), None)
+    }
+}
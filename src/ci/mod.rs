@@ -0,0 +1,219 @@
+mod azure;
+mod buildkite;
+mod circleci;
+mod github;
+mod gitlab;
+mod jenkins;
+
+use std::collections::HashMap;
+use std::error::Error;
+use clap::builder::PossibleValuesParser;
+use clap::{arg, Arg, ArgMatches};
+use crate::git::GitMetadata;
+
+// ========= CI Metadata =========
+/// Looks up an environment variable, so providers can be tested without changing the process environment.
+pub type Env<'a> = &'a dyn Fn(&str) -> Option<String>;
+
+/// What the CI system, or the git checkout, tells about the run an event is sent from.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CiMetadata {
+    /// The URL of the run or project, used as the event source when `--source` is not given.
+    /// `None` for a git checkout without a web remote, which still gives custom data.
+    pub source: Option<String>,
+    /// Added to the event's custom data, beneath any given with `--custom`.
+    pub custom_data: Vec<(String, String)>,
+    /// The environment a deployment job targets, for `--envid`, `--envname` and `--envsource`.
+    pub environment: Option<CiEnvironment>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CiEnvironment {
+    pub id: String,
+    pub name: Option<String>,
+    pub source: Option<String>,
+}
+
+/// A CI system that events can be filled from. Add a provider to [`PROVIDERS`] to support another one.
+pub trait CiProvider {
+    /// The name given to `--ci`.
+    fn name(&self) -> &'static str;
+
+    /// Maps the CI system's variables to the event defaults, returning `None` when not running on it.
+    fn metadata(&self, env: Env) -> Option<CiMetadata>;
+}
+
+/// The supported CI systems, in the order they are detected in.
+const PROVIDERS: [&dyn CiProvider; 6] = [
+    &github::GitHub,
+    &gitlab::GitLab,
+    &jenkins::Jenkins,
+    &azure::Azure,
+    &circleci::CircleCi,
+    &buildkite::Buildkite,
+];
+
+pub fn ci_args() -> [Arg; 1] {
+    let names = std::iter::once("auto").chain(PROVIDERS.iter().map(|provider| provider.name()));
+    [
+        arg!(--ci <PROVIDER> "Fill the source and custom data from the CI system's environment, auto detects the CI system")
+            .env("CDEVENT_CI")
+            .value_parser(PossibleValuesParser::new(names)),
+    ]
+}
+
+pub fn ci_parse(matches: &ArgMatches) -> Result<Option<CiMetadata>, Box<dyn Error + Send + Sync + 'static>> {
+    match matches.get_one::<String>("ci") {
+        Some(name) => metadata(name, &|name: &str| std::env::var(name).ok()),
+        None => Ok(None),
+    }
+}
+
+/// The metadata of the named CI system, or of the first one detected for `auto`.
+fn metadata(name: &str, env: Env) -> Result<Option<CiMetadata>, Box<dyn Error + Send + Sync + 'static>> {
+    if name == "auto" {
+        return Ok(PROVIDERS.iter().find_map(|provider| provider.metadata(env)));
+    }
+    let provider = PROVIDERS.iter()
+        .find(|provider| provider.name() == name)
+        .ok_or_else(|| format!("unknown CI system `{name}`"))?;
+    provider.metadata(env)
+        .map(Some)
+        .ok_or_else(|| format!("--ci {name} was given, but its environment variables are not set").into())
+}
+
+/// Falls back to the git checkout for what the CI system does not tell, so the CI system's values take precedence.
+pub fn with_git(ci: Option<CiMetadata>, git: Option<GitMetadata>) -> Option<CiMetadata> {
+    let Some(git) = git else {
+        return ci;
+    };
+    let Some(mut ci) = ci else {
+        return Some(git.to_ci_metadata());
+    };
+    let mut custom_data = git.custom_data();
+    custom_data.append(&mut ci.custom_data);
+    ci.custom_data = custom_data;
+    Some(ci)
+}
+
+/// The `--source`, or the source of the CI run when it is not given.
+pub fn source(source: Option<&String>, ci: Option<&CiMetadata>) -> Result<String, Box<dyn Error + Send + Sync + 'static>> {
+    source.cloned()
+        .or_else(|| ci.and_then(|ci| ci.source.clone()))
+        .ok_or_else(|| "--source is required when it cannot be taken from the CI system with --ci or from the git remote with --git".into())
+}
+
+/// The custom data of the CI run, with the `--custom` data replacing any of the same name.
+pub fn custom_data(custom_data: Option<HashMap<String, String>>, ci: Option<&CiMetadata>) -> Option<HashMap<String, String>> {
+    let Some(ci) = ci else {
+        return custom_data;
+    };
+    let mut merged: HashMap<String, String> = ci.custom_data.iter().cloned().collect();
+    merged.extend(custom_data.unwrap_or_default());
+    Some(merged)
+}
+
+/// Builds the custom data from the values that are set.
+fn present(values: &[(&str, Option<String>)]) -> Vec<(String, String)> {
+    values.iter()
+        .filter_map(|(name, value)| value.as_ref().filter(|value| !value.is_empty()).map(|value| (name.to_string(), value.clone())))
+        .collect()
+}
+
+/// An environment with only the given variables.
+#[cfg(test)]
+fn env_of(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+    let vars: HashMap<String, String> = vars.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect();
+    move |name| vars.get(name).cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ci() -> CiMetadata {
+        CiMetadata {
+            source: Some(String::from("https://github.com/acme/app/actions/runs/1")),
+            custom_data: vec![(String::from("ciRunId"), String::from("1")), (String::from("team"), String::from("ci"))],
+            environment: None,
+        }
+    }
+
+    #[test]
+    fn source_when_flag_and_ci_then_flag(){
+        assert_eq!(source(Some(&String::from("zsh")), Some(&ci())).unwrap(), "zsh")
+    }
+
+    #[test]
+    fn source_when_only_ci_then_ci_source(){
+        assert_eq!(source(None, Some(&ci())).unwrap(), "https://github.com/acme/app/actions/runs/1")
+    }
+
+    #[test]
+    fn source_when_neither_then_error(){
+        assert!(source(None, None).is_err())
+    }
+
+    #[test]
+    fn custom_data_when_flag_and_ci_then_flag_replaces_ci(){
+        let custom = HashMap::from([(String::from("team"), String::from("team1"))]);
+
+        let merged = custom_data(Some(custom), Some(&ci())).unwrap();
+
+        assert_eq!(merged.get("team").unwrap(), "team1");
+        assert_eq!(merged.get("ciRunId").unwrap(), "1");
+    }
+
+    #[test]
+    fn with_git_when_ci_and_git_then_ci_source_and_both_custom_data(){
+        let git = GitMetadata {
+            repository: Some(String::from("https://github.com/acme/app")),
+            commit: Some(String::from("ffac537e6cbbf934b08745a378932722df287a53")),
+            ..GitMetadata::default()
+        };
+
+        let metadata = with_git(Some(ci()), Some(git)).unwrap();
+        let merged = custom_data(None, Some(&metadata)).unwrap();
+
+        assert_eq!(metadata.source.as_deref(), Some("https://github.com/acme/app/actions/runs/1"));
+        assert_eq!(merged.get("gitCommit").unwrap(), "ffac537e6cbbf934b08745a378932722df287a53");
+        assert_eq!(merged.get("ciRunId").unwrap(), "1");
+    }
+
+    #[test]
+    fn with_git_when_only_git_then_repository_source(){
+        let git = GitMetadata { repository: Some(String::from("https://github.com/acme/app")), ..GitMetadata::default() };
+
+        assert_eq!(with_git(None, Some(git)).unwrap().source.as_deref(), Some("https://github.com/acme/app"))
+    }
+
+    #[test]
+    fn with_git_when_no_remote_then_source_required_and_custom_data_kept(){
+        let git = GitMetadata { commit: Some(String::from("ffac537e6cbbf934b08745a378932722df287a53")), ..GitMetadata::default() };
+
+        let metadata = with_git(None, Some(git));
+
+        assert!(source(None, metadata.as_ref()).is_err());
+        assert_eq!(source(Some(&String::from("zsh")), metadata.as_ref()).unwrap(), "zsh");
+        assert_eq!(custom_data(None, metadata.as_ref()).unwrap().get("gitCommit").unwrap(), "ffac537e6cbbf934b08745a378932722df287a53");
+    }
+
+    #[test]
+    fn metadata_when_auto_then_detected_provider(){
+        let env = env_of(&[("CIRCLECI", "true"), ("CIRCLE_BUILD_URL", "https://circleci.com/gh/acme/app/7")]);
+
+        let metadata = metadata("auto", &env).unwrap().unwrap();
+
+        assert!(metadata.custom_data.contains(&(String::from("ciProvider"), String::from("circleci"))));
+    }
+
+    #[test]
+    fn metadata_when_auto_and_no_ci_then_none(){
+        assert_eq!(metadata("auto", &env_of(&[])).unwrap(), None)
+    }
+
+    #[test]
+    fn metadata_when_named_provider_not_running_then_error(){
+        assert!(metadata("jenkins", &env_of(&[("GITHUB_ACTIONS", "true")])).is_err())
+    }
+}
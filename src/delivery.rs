@@ -0,0 +1,73 @@
+use std::error::Error;
+use cloudevents::Event;
+use crate::endpoint::Endpoint;
+use crate::{file, http};
+#[cfg(feature = "amqp")]
+use crate::amqp;
+#[cfg(feature = "kafka")]
+use crate::kafka;
+#[cfg(feature = "mqtt")]
+use crate::mqtt;
+#[cfg(feature = "nats")]
+use crate::nats;
+
+/// The outcome of sending an event to one endpoint.
+#[derive(Clone)]
+pub struct EndpointResult {
+    pub url: String,
+    pub outcome: Result<String, String>,
+}
+
+/// Sends the events to every endpoint in turn, collecting the result of each.
+///
+/// HTTP endpoints are sent with the `client`, which callers delivering many events share so its connections are reused.
+pub async fn deliver(client: &reqwest::Client, endpoints: &[Endpoint], events: &[Event]) -> Vec<EndpointResult> {
+    let mut results = Vec::with_capacity(endpoints.len());
+    for endpoint in endpoints {
+        let outcome = match endpoint.scheme() {
+            #[cfg(feature = "kafka")]
+            "kafka" => kafka::send(endpoint, events).await,
+            #[cfg(not(feature = "kafka"))]
+            "kafka" => Err(not_built_with("kafka")),
+            #[cfg(feature = "nats")]
+            "nats" => nats::send(endpoint, events).await,
+            #[cfg(not(feature = "nats"))]
+            "nats" => Err(not_built_with("nats")),
+            #[cfg(feature = "amqp")]
+            "amqp" | "amqps" => amqp::send(endpoint, events).await,
+            #[cfg(not(feature = "amqp"))]
+            "amqp" | "amqps" => Err(not_built_with("amqp")),
+            #[cfg(feature = "mqtt")]
+            "mqtt" => mqtt::send(endpoint, events).await,
+            #[cfg(not(feature = "mqtt"))]
+            "mqtt" => Err(not_built_with("mqtt")),
+            "file" => file::append(endpoint, events),
+            "-" => file::stdout(events),
+            _ => http::send(client, endpoint, events).await,
+        }
+        .map_err(|e| e.to_string());
+        results.push(EndpointResult {
+            url: endpoint.url.clone(),
+            outcome,
+        });
+    }
+    results
+}
+
+pub fn report(results: &[EndpointResult]) {
+    for result in results {
+        match &result.outcome {
+            Ok(status) => eprintln!("Endpoint {}: {status}", result.url),
+            Err(e) => eprintln!("Endpoint {}: failed: {e}", result.url),
+        }
+    }
+}
+
+pub fn succeeded(results: &[EndpointResult]) -> usize {
+    results.iter().filter(|result| result.outcome.is_ok()).count()
+}
+
+#[allow(dead_code)]
+fn not_built_with(feature: &str) -> Box<dyn Error + Send + Sync + 'static> {
+    format!("cdevent was built without the `{feature}` feature").into()
+}
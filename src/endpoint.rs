@@ -0,0 +1,256 @@
+use std::collections::HashMap;
+use std::error::Error;
+use clap::ArgMatches;
+use serde::Deserialize;
+use crate::auth::{self, OAuth2Args};
+use crate::headers;
+use crate::signing::{self, HmacArgs};
+
+// ========= Endpoints =========
+#[derive(Clone, Debug)]
+pub struct Endpoint {
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub oauth2: Option<OAuth2Args>,
+    pub hmac: Option<HmacArgs>,
+    pub content_mode: ContentMode,
+    pub compress: Option<Compression>,
+}
+
+impl Endpoint {
+    /// The URL scheme, which selects the transport the endpoint is sent to with, or `-` for stdout.
+    pub fn scheme(&self) -> &str {
+        if self.url == "-" {
+            return "-";
+        }
+        self.url.split_once("://").map(|(scheme, _)| scheme).unwrap_or("")
+    }
+}
+
+/// An endpoint listed under `endpoints` in the `--config` file.
+#[derive(Deserialize)]
+struct EndpointConfig {
+    url: String,
+    #[serde(default)]
+    headers: ConfigHeaders,
+    oauth2: Option<OAuth2Args>,
+    hmac: Option<HmacArgs>,
+    content_mode: Option<ContentMode>,
+    compress: Option<Compression>,
+}
+
+/// The headers of a config file endpoint, either a table, whose order is not kept,
+/// or a list of `Name: value` headers that keeps their order and allows repeated names.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ConfigHeaders {
+    Table(HashMap<String, String>),
+    List(Vec<String>),
+}
+
+impl Default for ConfigHeaders {
+    fn default() -> Self {
+        ConfigHeaders::List(Vec::new())
+    }
+}
+
+impl ConfigHeaders {
+    fn parse(self) -> Result<Vec<(String, String)>, Box<dyn Error + Send + Sync + 'static>> {
+        match self {
+            ConfigHeaders::Table(table) => Ok(table.into_iter().collect()),
+            ConfigHeaders::List(list) => Ok(list.iter()
+                .map(|header| headers::parse_headers(header))
+                .collect::<Result<Vec<_>, _>>()?
+                .into_iter()
+                .flatten()
+                .collect()),
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+struct ConfigFile {
+    #[serde(default)]
+    endpoints: Vec<EndpointConfig>,
+    policy: Option<Policy>,
+}
+
+/// How the CloudEvent is carried by the request, see the
+/// [HTTP protocol binding](https://github.com/cloudevents/spec/blob/v1.0.2/cloudevents/bindings/http-protocol-binding.md#3-http-message-mapping).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentMode {
+    /// Attributes in `ce-` headers and the CDEvent as the body.
+    #[default]
+    Binary,
+    /// The whole CloudEvent as an `application/cloudevents+json` body.
+    Structured,
+    /// A JSON array of CloudEvents as an `application/cloudevents-batch+json` body.
+    Batch,
+}
+
+impl ContentMode {
+    fn parse(s: &str) -> ContentMode {
+        match s {
+            "structured" => ContentMode::Structured,
+            "batch" => ContentMode::Batch,
+            _ => ContentMode::Binary,
+        }
+    }
+}
+
+/// The `Content-Encoding` HTTP request bodies are compressed with.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    fn parse(s: &str) -> Compression {
+        match s {
+            "zstd" => Compression::Zstd,
+            _ => Compression::Gzip,
+        }
+    }
+
+    pub fn encoding(&self) -> &'static str {
+        match self {
+            Compression::Gzip => "gzip",
+            Compression::Zstd => "zstd",
+        }
+    }
+}
+
+/// Whether an event counts as delivered when every endpoint accepted it, or at least one did.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Policy {
+    All,
+    Any,
+}
+
+impl Policy {
+    pub fn is_satisfied(&self, succeeded: usize, total: usize) -> bool {
+        match self {
+            Policy::All => succeeded == total,
+            Policy::Any => succeeded > 0,
+        }
+    }
+}
+
+/// Endpoints given with `-e` share the global headers, authentication and content mode arguments,
+/// while endpoints from the config file add their own on top of, or in place of, the global ones.
+pub fn endpoints_parse(matches: &ArgMatches) -> Result<(Vec<Endpoint>, Policy), Box<dyn Error + Send + Sync + 'static>> {
+    let headers: Vec<(String, String)> = matches.get_many::<Vec<(String, String)>>("headers")
+        .into_iter()
+        .flatten()
+        .flatten()
+        .cloned()
+        .collect();
+    let oauth2 = auth::oauth2_parse(matches);
+    let hmac = signing::hmac_parse(matches);
+    let content_mode = matches.get_one::<String>("content-mode").map(|s| ContentMode::parse(s));
+    let compress = matches.get_one::<String>("compress").map(|s| Compression::parse(s));
+    let config = match matches.get_one::<String>("config") {
+        Some(path) => read_config(path)?,
+        None => ConfigFile::default(),
+    };
+
+    let mut endpoints: Vec<Endpoint> = matches.get_many::<String>("endpoint")
+        .into_iter()
+        .flatten()
+        .map(|url| Endpoint {
+            url: url.clone(),
+            headers: headers.clone(),
+            oauth2: oauth2.clone(),
+            hmac: hmac.clone(),
+            content_mode: content_mode.unwrap_or_default(),
+            compress,
+        })
+        .collect();
+    for endpoint in config.endpoints {
+        endpoints.push(Endpoint {
+            headers: headers.iter().cloned().chain(endpoint.headers.parse()?).collect(),
+            url: endpoint.url,
+            oauth2: endpoint.oauth2.or_else(|| oauth2.clone()),
+            hmac: endpoint.hmac.or_else(|| hmac.clone()),
+            content_mode: endpoint.content_mode.or(content_mode).unwrap_or_default(),
+            compress: endpoint.compress.or(compress),
+        });
+    }
+    if endpoints.is_empty() {
+        return Err("no endpoint given, use -e or add endpoints to the --config file".into());
+    }
+
+    let policy = match matches.get_one::<String>("policy").map(String::as_str) {
+        Some("any") => Policy::Any,
+        Some(_) => Policy::All,
+        None => config.policy.unwrap_or(Policy::All),
+    };
+    Ok((endpoints, policy))
+}
+
+/// Reads the `--config` file, in any format the `config` crate supports, eg. TOML, YAML or JSON.
+fn read_config(path: &str) -> Result<ConfigFile, Box<dyn Error + Send + Sync + 'static>> {
+    let config = config::Config::builder()
+        .add_source(config::File::with_name(path))
+        .build()?
+        .try_deserialize()?;
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn policy_all_when_one_failed_then_not_satisfied(){
+        assert!(!Policy::All.is_satisfied(1, 2))
+    }
+
+    #[test]
+    fn policy_any_when_one_succeeded_then_satisfied(){
+        assert!(Policy::Any.is_satisfied(1, 2))
+    }
+
+    #[test]
+    fn policy_any_when_none_succeeded_then_not_satisfied(){
+        assert!(!Policy::Any.is_satisfied(0, 2))
+    }
+
+    #[test]
+    fn read_config_when_endpoints_then_endpoints_with_headers_and_auth(){
+        let path = std::env::temp_dir().join(format!("cdevent-test-{}-endpoints.toml", std::process::id()));
+        std::fs::write(&path, r#"
+policy = "any"
+
+[[endpoints]]
+url = "https://broker.internal/events"
+headers = { x-team = "team1" }
+
+[[endpoints]]
+url = "https://analytics.vendor.com/events"
+headers = ["X-Tag: b", "X-Tag: a", "Accept=\"text/plain, application/json\""]
+hmac = { secret = "s3cret" }
+content_mode = "structured"
+compress = "zstd"
+"#).unwrap();
+
+        let config = read_config(path.to_str().unwrap()).unwrap();
+
+        assert_eq!(config.policy, Some(Policy::Any));
+        let mut endpoints = config.endpoints.into_iter();
+        assert_eq!(endpoints.next().unwrap().headers.parse().unwrap(), vec![(String::from("x-team"), String::from("team1"))]);
+        let vendor = endpoints.next().unwrap();
+        assert_eq!(vendor.headers.parse().unwrap(), vec![
+            (String::from("X-Tag"), String::from("b")),
+            (String::from("X-Tag"), String::from("a")),
+            (String::from("Accept"), String::from("text/plain, application/json")),
+        ]);
+        assert_eq!(vendor.hmac.as_ref().unwrap().prefix, "sha256=");
+        assert_eq!(vendor.content_mode, Some(ContentMode::Structured));
+        assert_eq!(vendor.compress, Some(Compression::Zstd));
+    }
+}
@@ -0,0 +1,379 @@
+use std::collections::{HashMap, VecDeque};
+use std::error::Error;
+use std::ffi::OsString;
+use std::process::{ExitCode, ExitStatus, Stdio};
+use std::time::{Duration, Instant};
+use cdevents_sdk::{CDEvent, Subject, pipelinerun_finished_0_1_1, pipelinerun_started_0_1_1, taskrun_finished_0_1_1, taskrun_started_0_1_1};
+use clap::{arg, Arg, ArgMatches};
+use cloudevents::Event;
+use serde_json::{json, Map, Value};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::{ChildStderr, Command};
+use uuid::Uuid;
+use crate::args;
+use crate::chain::{self, ChainArgs};
+use crate::ci::{self, CiMetadata};
+use crate::id::{self, IdStrategy};
+
+// ========= Exec =========
+
+/// What running the command is reported as.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RunSubject {
+    TaskRun,
+    PipelineRun,
+}
+
+impl RunSubject {
+    fn parse(s: &str) -> RunSubject {
+        match s {
+            "pipelinerun" => RunSubject::PipelineRun,
+            _ => RunSubject::TaskRun,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ExecArgs {
+    pub id_strategy: IdStrategy,
+    pub source: String,
+    pub subject: RunSubject,
+    pub name: String,
+    pub subject_id: String,
+    pub url: Option<String>,
+    pub custom_data: Option<HashMap<String, String>>,
+    /// How many of the last lines of stderr are added to the finished event's errors.
+    pub stderr_lines: usize,
+    pub command: Vec<OsString>,
+}
+
+/// How the command ended.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Finished {
+    /// `success`, `failure` when the command exited with an error, or `error` when it could not run to the end.
+    pub outcome: &'static str,
+    pub errors: Option<String>,
+    pub exit_code: u8,
+    pub duration: Duration,
+}
+
+impl Finished {
+    pub fn exit_code(&self) -> ExitCode {
+        ExitCode::from(self.exit_code)
+    }
+}
+
+pub fn exec_args() -> [Arg; 6] {
+    [
+        arg!(--subject <SUBJECT> "What running the command is reported as")
+            .value_parser(["taskrun", "pipelinerun"])
+            .default_value("taskrun"),
+        arg!(--name <NAME> "The name of the task or pipeline").required(true),
+        arg!(--subid <SUBJECT_ID> "The unique ID of the run [default: a generated UUID]"),
+        arg!(--url <URL> "The URL of the run"),
+        arg!(--"stderr-lines" <LINES> "Add the last lines of the command's stderr to the finished event's errors")
+            .value_parser(clap::value_parser!(usize))
+            .default_value("0"),
+        arg!(--custom <CUSTOM_DATA> "Additional data added to the events").value_parser(parse_custom_data),
+    ]
+}
+
+fn parse_custom_data(s: &str) -> Result<Vec<(String, String)>, Box<dyn Error + Send + Sync + 'static>>
+{
+    args::parse_comma_delimited(s, "No custom data provided")
+}
+
+/// Parses the arguments, taking the source and custom data from the CI run where they are not given.
+pub fn exec_parse(matches: &ArgMatches, ci: Option<&CiMetadata>) -> Result<ExecArgs, Box<dyn Error + Send + Sync + 'static>> {
+    let custom_data = matches.get_one::<Vec<(String, String)>>("custom")
+        .map(|custom| custom.iter().cloned().collect());
+    Ok(ExecArgs {
+        id_strategy: IdStrategy::parse(matches.get_one::<String>("id-strategy").unwrap()),
+        source: ci::source(matches.get_one::<String>("source"), ci)?,
+        subject: RunSubject::parse(matches.get_one::<String>("subject").unwrap()),
+        name: matches.get_one::<String>("name").unwrap().clone(),
+        subject_id: matches.get_one::<String>("subid").cloned()
+            .unwrap_or_else(|| Uuid::new_v4().to_string()),
+        url: matches.get_one::<String>("url").cloned(),
+        custom_data: ci::custom_data(custom_data, ci),
+        stderr_lines: *matches.get_one::<usize>("stderr-lines").unwrap(),
+        command: matches.get_many::<OsString>("command").unwrap().cloned().collect(),
+    })
+}
+
+/// The started event, sent before the command runs.
+pub fn started_event(args: &ExecArgs, chain: &ChainArgs) -> Result<Event, Box<dyn Error + Send + Sync + 'static>> {
+    let subject = match args.subject {
+        RunSubject::TaskRun => Subject::from(taskrun_started_0_1_1::Content {
+            pipeline_run: None,
+            task_name: Some(args.name.clone().try_into()?),
+            url: args.url.clone().map(TryInto::try_into).transpose()?,
+        }),
+        // The pipelinerun started event requires a URL, the run's source is the closest to one.
+        RunSubject::PipelineRun => Subject::from(pipelinerun_started_0_1_1::Content {
+            pipeline_name: args.name.clone().try_into()?,
+            url: args.url.clone().unwrap_or_else(|| args.source.clone()).try_into()?,
+        }),
+    };
+    to_cloud_event(args, subject, Map::new(), chain)
+}
+
+/// The finished event, with the exit code and duration of the command in the custom data.
+pub fn finished_event(args: &ExecArgs, finished: &Finished, chain: &ChainArgs) -> Result<Event, Box<dyn Error + Send + Sync + 'static>> {
+    let subject = match args.subject {
+        RunSubject::TaskRun => Subject::from(taskrun_finished_0_1_1::Content {
+            errors: finished.errors.clone(),
+            outcome: Some(finished.outcome.to_string()),
+            pipeline_run: None,
+            task_name: Some(args.name.clone().try_into()?),
+            url: args.url.clone().map(TryInto::try_into).transpose()?,
+        }),
+        RunSubject::PipelineRun => Subject::from(pipelinerun_finished_0_1_1::Content {
+            errors: finished.errors.clone(),
+            outcome: Some(finished.outcome.to_string()),
+            pipeline_name: Some(args.name.clone().try_into()?),
+            url: args.url.clone().map(TryInto::try_into).transpose()?,
+        }),
+    };
+    let mut custom_data = Map::new();
+    custom_data.insert(String::from("exitCode"), json!(finished.exit_code));
+    custom_data.insert(String::from("durationMs"), json!(finished.duration.as_millis() as u64));
+    to_cloud_event(args, subject, custom_data, chain)
+}
+
+/// Builds the CloudEvent of the run in the chain, adding the `--custom` data to `custom_data`.
+fn to_cloud_event(args: &ExecArgs, subject: Subject, mut custom_data: Map<String, Value>, chain: &ChainArgs) -> Result<Event, Box<dyn Error + Send + Sync + 'static>> {
+    for (name, value) in args.custom_data.iter().flatten() {
+        custom_data.entry(name.clone()).or_insert_with(|| json!(value));
+    }
+    let mut cd_event = CDEvent::from(
+        subject
+            .with_id(args.subject_id.clone().try_into()?)
+            .with_source(args.source.clone().try_into()?)
+    )
+        .with_source(args.source.clone().try_into()?);
+    if !custom_data.is_empty() {
+        cd_event = cd_event.with_custom_data(Value::Object(custom_data));
+    }
+    chain::to_cloud_event(cd_event, chain, |document| id::generate(args.id_strategy, document, false))
+}
+
+/// Runs the command with the inherited stdin and stdout, echoing its stderr while keeping the last `--stderr-lines`.
+pub async fn run(args: &ExecArgs) -> Finished {
+    let start = Instant::now();
+    let (program, arguments) = args.command.split_first().expect("clap requires a command");
+    let mut command = Command::new(program);
+    command.args(arguments);
+    if args.stderr_lines > 0 {
+        command.stderr(Stdio::piped());
+    }
+    let mut child = match command.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            // The exit codes shells use for a command that is not found or cannot be executed.
+            let exit_code = match e.kind() {
+                std::io::ErrorKind::NotFound => 127,
+                std::io::ErrorKind::PermissionDenied => 126,
+                _ => 1,
+            };
+            return Finished {
+                outcome: "error",
+                errors: Some(format!("Could not run {}: {e}", program.to_string_lossy())),
+                exit_code,
+                duration: start.elapsed(),
+            };
+        }
+    };
+
+    let stderr = drain_stderr(child.stderr.take(), args.stderr_lines);
+    let exit = async {
+        tokio::select! {
+            status = child.wait() => (status, None),
+            signal = interrupted() => {
+                // Ctrl-C reaches the whole process group, but a SIGTERM sent to cdevent has to be passed on.
+                if signal == SIGTERM {
+                    let _ = child.start_kill();
+                }
+                (child.wait().await, Some(signal))
+            }
+        }
+    };
+    let (tail, (status, signal)) = tokio::join!(stderr, exit);
+    let duration = start.elapsed();
+    let errors = Some(tail.join("\n")).filter(|errors| !errors.is_empty());
+    if let Some(signal) = signal {
+        return Finished {
+            outcome: "error",
+            errors: errors.or_else(|| Some(format!("cdevent was interrupted by signal {signal}"))),
+            exit_code: 128 + signal,
+            duration,
+        };
+    }
+    match status {
+        Ok(status) if status.success() => Finished { outcome: "success", errors: None, exit_code: 0, duration },
+        Ok(status) => match status.code() {
+            Some(code) => Finished { outcome: "failure", errors, exit_code: exit_code(code), duration },
+            None => Finished {
+                outcome: "error",
+                errors: errors.or_else(|| Some(format!("{} was terminated by a signal", program.to_string_lossy()))),
+                exit_code: signal_exit_code(status),
+                duration,
+            },
+        },
+        Err(e) => Finished { outcome: "error", errors: Some(e.to_string()), exit_code: 1, duration },
+    }
+}
+
+/// Echoes the command's stderr, returning its last `lines`.
+async fn drain_stderr(stderr: Option<ChildStderr>, lines: usize) -> Vec<String> {
+    let Some(stderr) = stderr else {
+        return Vec::new();
+    };
+    let mut tail = VecDeque::with_capacity(lines);
+    let mut stderr = BufReader::new(stderr);
+    let mut line = Vec::new();
+    loop {
+        match stderr.read_until(b'\n', &mut line).await {
+            Ok(0) => break,
+            Ok(_) => {
+                let text = String::from_utf8_lossy(&line).trim_end().to_string();
+                eprintln!("{text}");
+                if tail.len() == lines {
+                    tail.pop_front();
+                }
+                tail.push_back(text);
+                line.clear();
+            }
+            Err(e) => {
+                eprintln!("Could not read the command's stderr: {e}");
+                break;
+            }
+        }
+    }
+    Vec::from(tail)
+}
+
+/// The exit code of a command that exited with `code`, which is 1 when it does not fit an exit code, eg. on Windows.
+fn exit_code(code: i32) -> u8 {
+    u8::try_from(code).unwrap_or(1)
+}
+
+const SIGINT: u8 = 2;
+const SIGTERM: u8 = 15;
+
+/// Waits for Ctrl-C or SIGTERM, returning the signal number. Waits forever when the signals cannot be listened to.
+#[cfg(unix)]
+async fn interrupted() -> u8 {
+    use tokio::signal::unix::{signal, SignalKind};
+    let (Ok(mut interrupt), Ok(mut terminate)) = (signal(SignalKind::interrupt()), signal(SignalKind::terminate())) else {
+        return std::future::pending().await;
+    };
+    tokio::select! {
+        _ = interrupt.recv() => SIGINT,
+        _ = terminate.recv() => SIGTERM,
+    }
+}
+
+#[cfg(not(unix))]
+async fn interrupted() -> u8 {
+    match tokio::signal::ctrl_c().await {
+        Ok(()) => SIGINT,
+        Err(_) => std::future::pending().await,
+    }
+}
+
+/// The exit code shells give a command terminated by a signal.
+#[cfg(unix)]
+fn signal_exit_code(status: ExitStatus) -> u8 {
+    use std::os::unix::process::ExitStatusExt;
+    status.signal().map(|signal| 128 + signal as u8).unwrap_or(1)
+}
+
+#[cfg(not(unix))]
+fn signal_exit_code(_status: ExitStatus) -> u8 {
+    1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cloudevents::AttributesReader;
+
+    fn args(command: &[&str]) -> ExecArgs {
+        ExecArgs {
+            id_strategy: IdStrategy::Uuid4,
+            source: String::from("https://ci.acme.com/runs/1"),
+            subject: RunSubject::TaskRun,
+            name: String::from("lint"),
+            subject_id: String::from("run-1"),
+            url: None,
+            custom_data: Some(HashMap::from([(String::from("team"), String::from("web"))])),
+            stderr_lines: 2,
+            command: command.iter().map(OsString::from).collect(),
+        }
+    }
+
+    fn data(event: &Event) -> Value {
+        match event.data() {
+            Some(cloudevents::Data::Json(data)) => data.clone(),
+            _ => panic!("no JSON data"),
+        }
+    }
+
+    #[test]
+    fn started_event_when_taskrun_then_task_name_and_custom_data(){
+        let event = started_event(&args(&["true"]), &ChainArgs::default()).unwrap();
+
+        assert_eq!(event.ty(), "dev.cdevents.taskrun.started.0.1.1");
+        assert_eq!(data(&event)["subject"]["content"]["taskName"], "lint");
+        assert_eq!(data(&event)["customData"]["team"], "web");
+    }
+
+    #[test]
+    fn finished_event_when_pipelinerun_then_pipeline_name_outcome_and_exit_code(){
+        let args = ExecArgs { subject: RunSubject::PipelineRun, ..args(&["true"]) };
+        let finished = Finished { outcome: "failure", errors: None, exit_code: 3, duration: Duration::from_millis(20) };
+
+        let event = finished_event(&args, &finished, &ChainArgs::default()).unwrap();
+
+        assert_eq!(event.ty(), "dev.cdevents.pipelinerun.finished.0.1.1");
+        assert_eq!(data(&event)["subject"]["content"]["pipelineName"], "lint");
+        assert_eq!(data(&event)["subject"]["content"]["outcome"], "failure");
+        assert_eq!(data(&event)["customData"]["exitCode"], 3);
+    }
+
+    #[test]
+    fn exit_code_when_out_of_range_then_1(){
+        assert_eq!(exit_code(256), 1);
+        assert_eq!(exit_code(-1), 1);
+        assert_eq!(exit_code(255), 255);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn run_when_command_fails_then_failure_with_stderr_tail(){
+        let finished = run(&args(&["sh", "-c", "echo one >&2; echo two >&2; echo three >&2; exit 3"])).await;
+
+        assert_eq!(finished.outcome, "failure");
+        assert_eq!(finished.exit_code, 3);
+        assert_eq!(finished.errors.as_deref(), Some("two\nthree"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn run_when_command_succeeds_then_success(){
+        let finished = run(&args(&["true"])).await;
+
+        assert_eq!(finished.outcome, "success");
+        assert_eq!(finished.exit_code, 0);
+    }
+
+    #[tokio::test]
+    async fn run_when_command_not_found_then_error_and_127(){
+        let finished = run(&args(&["cdevent-test-no-such-command"])).await;
+
+        assert_eq!(finished.outcome, "error");
+        assert_eq!(finished.exit_code, 127);
+        assert!(finished.errors.unwrap().contains("cdevent-test-no-such-command"));
+    }
+}
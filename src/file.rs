@@ -0,0 +1,60 @@
+use std::error::Error;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use cloudevents::Event;
+use crate::binding;
+use crate::endpoint::Endpoint;
+
+/// Appends the events as newline delimited structured CloudEvents to the file of a `file:///path` endpoint.
+///
+/// Each event is written with a single call, so events appended by concurrent invocations are not interleaved.
+pub fn append(endpoint: &Endpoint, events: &[Event]) -> Result<String, Box<dyn Error + Send + Sync + 'static>> {
+    let path = Path::new(endpoint.url.trim_start_matches("file://"));
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    for event in events {
+        file.write_all(&line(event)?)?;
+    }
+    Ok(format!("appended to {}", path.display()))
+}
+
+/// Writes the events as newline delimited structured CloudEvents to stdout, for the `-` endpoint.
+pub fn stdout(events: &[Event]) -> Result<String, Box<dyn Error + Send + Sync + 'static>> {
+    let mut stdout = std::io::stdout().lock();
+    for event in events {
+        stdout.write_all(&line(event)?)?;
+    }
+    stdout.flush()?;
+    Ok(String::from("written to stdout"))
+}
+
+fn line(event: &Event) -> Result<Vec<u8>, Box<dyn Error + Send + Sync + 'static>> {
+    let mut line = binding::structured(event)?;
+    line.push(b'\n');
+    Ok(line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binding::test_endpoint as endpoint;
+    use crate::binding::test_event as event;
+    use crate::endpoint::ContentMode;
+
+    #[test]
+    fn append_when_called_twice_then_one_line_per_event(){
+        let path = std::env::temp_dir().join(format!("cdevent-test-{}", std::process::id())).join("events.ndjson");
+        let _ = std::fs::remove_file(&path);
+        let endpoint = endpoint(format!("file://{}", path.display()), ContentMode::Structured);
+
+        append(&endpoint, &[event("1")]).unwrap();
+        append(&endpoint, &[event("2")]).unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let events: Vec<Event> = contents.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(events, vec![event("1"), event("2")]);
+    }
+}
@@ -0,0 +1,88 @@
+use std::error::Error;
+use cloudevents::Event;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use crate::{args, template};
+
+/// Parses a single `-H` value, either curl style `Name: value` or a comma delimited list of `Name=value` pairs.
+pub fn parse_headers(s: &str) -> Result<Vec<(String, String)>, Box<dyn Error + Send + Sync + 'static>>
+{
+    let headers = match s.find([':', '=']) {
+        Some(pos) if s[pos..].starts_with(':') => vec![(s[..pos].trim().to_string(), s[pos + 1..].trim().to_string())],
+        _ => args::parse_quoted_comma_delimited(s, "No headers provided")?,
+    };
+    for (name, value) in &headers {
+        validate_header(name, value)?;
+    }
+    Ok(headers)
+}
+
+fn validate_header(name: &str, value: &str) -> Result<(), Box<dyn Error + Send + Sync + 'static>>
+{
+    HeaderName::try_from(name).map_err(|_| format!("invalid header name `{name}`"))?;
+    HeaderValue::from_str(value).map_err(|_| format!("invalid value for header `{name}`"))?;
+    Ok(())
+}
+
+/// Builds the request headers for an event, rendering `{{...}}` templates in the values
+/// and keeping repeated names as separate values.
+pub fn to_header_map(headers: &[(String, String)], event: &Event) -> Result<HeaderMap, Box<dyn Error + Send + Sync + 'static>>
+{
+    let mut header_map = HeaderMap::new();
+    for (name, value) in headers {
+        let value = template::render(value, event)?;
+        let value = HeaderValue::from_str(&value).map_err(|_| format!("invalid value `{value}` rendered for header `{name}`"))?;
+        header_map.append(HeaderName::try_from(name.as_str())?, value);
+    }
+    Ok(header_map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cloudevents::{EventBuilder, EventBuilderV10};
+
+    #[test]
+    fn parse_headers_when_colon_syntax_then_single_header(){
+        let result = parse_headers("Authorization: Bearer a=b, c");
+        assert_eq!(result.unwrap(), vec![(String::from("Authorization"), String::from("Bearer a=b, c"))])
+    }
+
+    #[test]
+    fn parse_headers_when_key_values_then_headers(){
+        let result = parse_headers("x-team=team1,x-url=http://acme.com");
+        assert_eq!(result.unwrap(), vec![(String::from("x-team"), String::from("team1")), (String::from("x-url"), String::from("http://acme.com"))])
+    }
+
+    #[test]
+    fn parse_headers_when_quoted_value_with_comma_then_single_header(){
+        let result = parse_headers("accept=\"text/plain, application/json\"");
+        assert_eq!(result.unwrap(), vec![(String::from("accept"), String::from("text/plain, application/json"))])
+    }
+
+    #[test]
+    fn parse_headers_when_invalid_name_then_error(){
+        let result = parse_headers("bad header: value");
+        assert!(result.is_err())
+    }
+
+    #[test]
+    fn parse_headers_when_invalid_value_then_error(){
+        let result = parse_headers("x-bad=line\nbreak");
+        assert!(result.is_err())
+    }
+
+    #[test]
+    fn to_header_map_when_repeated_name_then_all_values_kept(){
+        let headers = vec![(String::from("x-tag"), String::from("a")), (String::from("x-tag"), String::from("b"))];
+        let header_map = to_header_map(&headers, &Event::default()).unwrap();
+        assert_eq!(header_map.get_all("x-tag").iter().collect::<Vec<_>>(), vec!["a", "b"])
+    }
+
+    #[test]
+    fn to_header_map_when_template_then_rendered_from_event(){
+        let event = EventBuilderV10::new().id("42").source("zsh").ty("dev.cdevents.service.deployed.0.1.1").build().unwrap();
+        let headers = vec![(String::from("x-event-id"), String::from("{{id}}"))];
+        let header_map = to_header_map(&headers, &event).unwrap();
+        assert_eq!(header_map.get("x-event-id").unwrap(), "42")
+    }
+}
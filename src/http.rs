@@ -0,0 +1,246 @@
+use std::error::Error;
+use std::io::Write;
+use cloudevents::Event;
+use cloudevents::binding::reqwest::RequestBuilderExt;
+use flate2::write::GzEncoder;
+use reqwest::StatusCode;
+use reqwest::header::{HeaderValue, CONTENT_ENCODING, CONTENT_TYPE};
+use crate::binding::{self, BATCH_CONTENT_TYPE, STRUCTURED_CONTENT_TYPE};
+use crate::endpoint::{Compression, ContentMode, Endpoint};
+use crate::{auth, headers, signing};
+#[cfg(unix)]
+use crate::unix;
+
+/// Posts the events to an HTTP endpoint in its content mode, returning the response status when it is a success.
+///
+/// In binary and structured mode each event is a request of its own, while batch mode sends them all in one request.
+/// Header templates in batch mode are rendered from the first event.
+/// Bodies are compressed when the endpoint has a compression, and signed after it, over the bytes that are sent.
+/// `unix:///run/events.sock:/path` endpoints are sent over the Unix domain socket instead of TCP.
+pub async fn send(client: &reqwest::Client, endpoint: &Endpoint, events: &[Event]) -> Result<String, Box<dyn Error + Send + Sync + 'static>> {
+    let url = request_url(endpoint);
+    let mut status = String::new();
+    match endpoint.content_mode {
+        ContentMode::Batch => {
+            let first = events.first().ok_or("no events to send")?;
+            let request = client
+                .post(&url)
+                .header(CONTENT_TYPE, BATCH_CONTENT_TYPE)
+                .body(serde_json::to_vec(events)?);
+            status = execute(client, endpoint, request, first).await?;
+        }
+        ContentMode::Structured => {
+            for event in events {
+                let request = client
+                    .post(&url)
+                    .header(CONTENT_TYPE, STRUCTURED_CONTENT_TYPE)
+                    .body(binding::structured(event)?);
+                status = execute(client, endpoint, request, event).await?;
+            }
+        }
+        ContentMode::Binary => {
+            for event in events {
+                let request = client
+                    .post(&url)
+                    .event(event.clone())?;
+                status = execute(client, endpoint, request, event).await?;
+            }
+        }
+    }
+    Ok(status)
+}
+
+async fn execute(client: &reqwest::Client, endpoint: &Endpoint, request: reqwest::RequestBuilder, event: &Event) -> Result<String, Box<dyn Error + Send + Sync + 'static>> {
+    let mut request = request.headers(headers::to_header_map(&endpoint.headers, event)?);
+    if let Some(oauth2) = &endpoint.oauth2 {
+        request = request.bearer_auth(auth::access_token(oauth2).await?);
+    }
+    let request = request.build()?;
+
+    let mut compressed_status = None;
+    if let Some(compression) = endpoint.compress {
+        let compressed = compress(compression, request.try_clone().ok_or("cannot compress a streaming request body")?)?;
+        compressed_status = Some(dispatch(client, endpoint, compressed).await?);
+    }
+    // An endpoint that cannot decode the body answers 415 Unsupported Media Type, and is sent it uncompressed instead.
+    let status = match compressed_status {
+        Some(status) if status != StatusCode::UNSUPPORTED_MEDIA_TYPE => status,
+        _ => dispatch(client, endpoint, request).await?,
+    };
+    if !status.is_success() {
+        return Err(format!("endpoint responded with {status}").into());
+    }
+    Ok(status.to_string())
+}
+
+/// Signs the request, now that its body is final, and sends it.
+async fn dispatch(client: &reqwest::Client, endpoint: &Endpoint, mut request: reqwest::Request) -> Result<StatusCode, Box<dyn Error + Send + Sync + 'static>> {
+    if let Some(hmac) = &endpoint.hmac {
+        signing::sign_request(hmac, &mut request)?;
+    }
+    let status = match endpoint.scheme() {
+        #[cfg(unix)]
+        "unix" => unix::execute(&endpoint.url, request).await?,
+        _ => client.execute(request).await?.status(),
+    };
+    Ok(status)
+}
+
+/// Replaces the body with its compressed bytes and sets the `Content-Encoding` header.
+fn compress(compression: Compression, mut request: reqwest::Request) -> Result<reqwest::Request, Box<dyn Error + Send + Sync + 'static>> {
+    let body = request.body().and_then(|body| body.as_bytes()).unwrap_or_default();
+    let compressed = match compression {
+        Compression::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(body)?;
+            encoder.finish()?
+        }
+        Compression::Zstd => zstd::encode_all(body, 0)?,
+    };
+    *request.body_mut() = Some(compressed.into());
+    request.headers_mut().insert(CONTENT_ENCODING, HeaderValue::from_static(compression.encoding()));
+    Ok(request)
+}
+
+fn request_url(endpoint: &Endpoint) -> String {
+    match endpoint.scheme() {
+        #[cfg(unix)]
+        "unix" => unix::request_url(&endpoint.url),
+        _ => endpoint.url.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binding::test_endpoint as endpoint;
+    use crate::binding::test_event as event;
+    use mockito::Matcher;
+
+    fn decompress(compression: Compression, body: &[u8]) -> Vec<u8> {
+        match compression {
+            Compression::Gzip => {
+                let mut decompressed = Vec::new();
+                std::io::Read::read_to_end(&mut flate2::read::GzDecoder::new(body), &mut decompressed).unwrap();
+                decompressed
+            }
+            Compression::Zstd => zstd::decode_all(body).unwrap(),
+        }
+    }
+
+    #[tokio::test]
+    async fn send_when_structured_then_cloudevent_json_body(){
+        let mut server = mockito::Server::new_async().await;
+        let mock = server.mock("POST", "/events")
+            .match_header("content-type", STRUCTURED_CONTENT_TYPE)
+            .match_body(Matcher::Json(serde_json::to_value(event("1")).unwrap()))
+            .with_status(202)
+            .create_async()
+            .await;
+
+        let status = send(&reqwest::Client::new(), &endpoint(format!("{}/events", server.url()), ContentMode::Structured), &[event("1")]).await;
+
+        assert_eq!(status.unwrap(), "202 Accepted");
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn send_when_batch_then_single_request_with_all_events(){
+        let mut server = mockito::Server::new_async().await;
+        let mock = server.mock("POST", "/events")
+            .match_header("content-type", BATCH_CONTENT_TYPE)
+            .match_body(Matcher::Json(serde_json::to_value([event("1"), event("2")]).unwrap()))
+            .expect(1)
+            .create_async()
+            .await;
+
+        let status = send(&reqwest::Client::new(), &endpoint(format!("{}/events", server.url()), ContentMode::Batch), &[event("1"), event("2")]).await;
+
+        assert!(status.is_ok());
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn send_when_endpoint_errors_then_error(){
+        let mut server = mockito::Server::new_async().await;
+        server.mock("POST", "/events").with_status(500).create_async().await;
+
+        let status = send(&reqwest::Client::new(), &endpoint(format!("{}/events", server.url()), ContentMode::Structured), &[event("1")]).await;
+
+        assert!(status.is_err());
+    }
+
+    #[tokio::test]
+    async fn send_when_gzip_then_compressed_body_and_content_encoding(){
+        let mut server = mockito::Server::new_async().await;
+        let mock = server.mock("POST", "/events")
+            .match_header("content-encoding", "gzip")
+            .match_request(|request| decompress(Compression::Gzip, request.body().unwrap()) == binding::structured(&event("1")).unwrap())
+            .with_status(202)
+            .create_async()
+            .await;
+        let endpoint = Endpoint {
+            compress: Some(Compression::Gzip),
+            ..endpoint(format!("{}/events", server.url()), ContentMode::Structured)
+        };
+
+        let status = send(&reqwest::Client::new(), &endpoint, &[event("1")]).await;
+
+        assert_eq!(status.unwrap(), "202 Accepted");
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn send_when_compressed_and_signed_then_signature_over_compressed_body(){
+        let mut server = mockito::Server::new_async().await;
+        let mock = server.mock("POST", "/events")
+            .match_header("content-encoding", "zstd")
+            .match_request(|request| {
+                let signature = format!("sha256={}", signing::sign(b"s3cret", request.body().unwrap()));
+                request.header("x-signature-256").first().is_some_and(|header| *header == signature.as_str())
+            })
+            .with_status(202)
+            .create_async()
+            .await;
+        let endpoint = Endpoint {
+            compress: Some(Compression::Zstd),
+            hmac: Some(signing::HmacArgs {
+                secret: String::from("s3cret"),
+                header: String::from("X-Signature-256"),
+                prefix: String::from("sha256="),
+            }),
+            ..endpoint(format!("{}/events", server.url()), ContentMode::Structured)
+        };
+
+        let status = send(&reqwest::Client::new(), &endpoint, &[event("1")]).await;
+
+        assert!(status.is_ok());
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn send_when_compression_unsupported_then_sent_uncompressed(){
+        let mut server = mockito::Server::new_async().await;
+        let compressed = server.mock("POST", "/events")
+            .match_header("content-encoding", "zstd")
+            .with_status(415)
+            .create_async()
+            .await;
+        let uncompressed = server.mock("POST", "/events")
+            .match_header("content-encoding", Matcher::Missing)
+            .match_body(Matcher::Json(serde_json::to_value(event("1")).unwrap()))
+            .with_status(202)
+            .create_async()
+            .await;
+        let endpoint = Endpoint {
+            compress: Some(Compression::Zstd),
+            ..endpoint(format!("{}/events", server.url()), ContentMode::Structured)
+        };
+
+        let status = send(&reqwest::Client::new(), &endpoint, &[event("1")]).await;
+
+        assert_eq!(status.unwrap(), "202 Accepted");
+        compressed.assert_async().await;
+        uncompressed.assert_async().await;
+    }
+}
@@ -0,0 +1,91 @@
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use ulid::Ulid;
+use uuid::Uuid;
+
+// ========= Event IDs =========
+/// How the CloudEvent ID is generated when `--id` is not given.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum IdStrategy {
+    #[default]
+    Uuid4,
+    Uuid7,
+    Ulid,
+    /// Derived from the event content, so retrying the same command produces the same ID.
+    Hash,
+}
+
+impl IdStrategy {
+    pub fn parse(s: &str) -> IdStrategy {
+        match s {
+            "uuid7" => IdStrategy::Uuid7,
+            "ulid" => IdStrategy::Ulid,
+            "hash" => IdStrategy::Hash,
+            _ => IdStrategy::Uuid4,
+        }
+    }
+}
+
+/// Generates an ID for the CDEvent.
+///
+/// The hash strategy leaves `context.id` out, and `context.timestamp` unless it was `timestamp_given`,
+/// as generated ones change on every run, and hex encodes the SHA-256 of the rest of the CDEvent's JSON.
+pub fn generate<T: Serialize>(strategy: IdStrategy, cd_event: &T, timestamp_given: bool) -> String {
+    match strategy {
+        IdStrategy::Uuid4 => Uuid::new_v4().to_string(),
+        IdStrategy::Uuid7 => Uuid::now_v7().to_string(),
+        IdStrategy::Ulid => Ulid::new().to_string(),
+        IdStrategy::Hash => {
+            let mut content = serde_json::to_value(cd_event).expect("a CDEvent serializes to JSON");
+            if let Some(context) = content.get_mut("context").and_then(|context| context.as_object_mut()) {
+                context.remove("id");
+                if !timestamp_given {
+                    context.remove("timestamp");
+                }
+            }
+            hex::encode(Sha256::digest(content.to_string()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn generate_when_hash_then_same_id_for_same_content_at_other_time(){
+        let first = json!({"context": {"id": "0", "timestamp": "2024-09-10T04:55:25Z", "source": "zsh"}, "subject": {"id": "xyz"}});
+        let retry = json!({"context": {"id": "0", "timestamp": "2024-09-10T04:56:00Z", "source": "zsh"}, "subject": {"id": "xyz"}});
+
+        assert_eq!(generate(IdStrategy::Hash, &first, false), generate(IdStrategy::Hash, &retry, false));
+    }
+
+    #[test]
+    fn generate_when_hash_and_timestamp_given_then_different_id_at_other_time(){
+        let first = json!({"context": {"timestamp": "2024-09-10T04:55:25Z", "source": "zsh"}, "subject": {"id": "xyz"}});
+        let later = json!({"context": {"timestamp": "2024-09-11T04:55:25Z", "source": "zsh"}, "subject": {"id": "xyz"}});
+
+        assert_ne!(generate(IdStrategy::Hash, &first, true), generate(IdStrategy::Hash, &later, true));
+    }
+
+    #[test]
+    fn generate_when_hash_and_content_differs_then_different_id(){
+        let first = json!({"context": {"source": "zsh"}, "subject": {"id": "xyz"}});
+        let other = json!({"context": {"source": "zsh"}, "subject": {"id": "abc"}});
+
+        assert_ne!(generate(IdStrategy::Hash, &first, false), generate(IdStrategy::Hash, &other, false));
+    }
+
+    #[test]
+    fn generate_when_uuid7_then_version_7(){
+        let id = generate(IdStrategy::Uuid7, &json!({}), false);
+
+        assert_eq!(Uuid::parse_str(&id).unwrap().get_version_num(), 7);
+    }
+
+    #[test]
+    fn generate_when_ulid_then_valid_ulid(){
+        assert!(Ulid::from_string(&generate(IdStrategy::Ulid, &json!({}), false)).is_ok())
+    }
+}
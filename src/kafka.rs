@@ -0,0 +1,108 @@
+use std::error::Error;
+use std::time::Duration;
+use cloudevents::Event;
+use rdkafka::ClientConfig;
+use rdkafka::message::{Header, OwnedHeaders};
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use crate::binding::{self, STRUCTURED_CONTENT_TYPE};
+use crate::broker;
+use crate::endpoint::{ContentMode, Endpoint};
+use crate::template;
+
+const SEND_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Publishes the events to a `kafka://broker[,broker]/topic` endpoint following the
+/// [Kafka protocol binding](https://github.com/cloudevents/spec/blob/v1.0.2/cloudevents/bindings/kafka-protocol-binding.md).
+///
+/// The `key` parameter sets the partition key from the event, either an attribute name such as `?key=subject`
+/// or a template such as `?key={{data.subject.content.environment.id}}`.
+pub async fn send(endpoint: &Endpoint, events: &[Event]) -> Result<String, Box<dyn Error + Send + Sync + 'static>> {
+    let target = endpoint.target();
+    if target.path.is_empty() {
+        return Err("no topic given, expected kafka://broker/topic".into());
+    }
+    let producer: FutureProducer = ClientConfig::new()
+        .set("bootstrap.servers", &target.host)
+        .set("message.timeout.ms", SEND_TIMEOUT.as_millis().to_string())
+        .create()?;
+
+    let mut delivered = String::new();
+    for event in events {
+        let key = target.param("key")
+            .map(|key| template::render_field(key, event))
+            .transpose()?;
+        let (headers, payload) = message(endpoint.content_mode, event)?;
+        let mut record: FutureRecord<str, [u8]> = FutureRecord::to(&target.path)
+            .payload(payload.as_slice())
+            .headers(headers);
+        if let Some(key) = &key {
+            record = record.key(key.as_str());
+        }
+        let (partition, offset) = producer.send(record, SEND_TIMEOUT)
+            .await
+            .map_err(|(e, _)| e)?;
+        delivered = format!("partition {partition}, offset {offset}");
+    }
+    Ok(delivered)
+}
+
+/// Headers and value of the Kafka message for the event.
+fn message(content_mode: ContentMode, event: &Event) -> Result<(OwnedHeaders, Vec<u8>), Box<dyn Error + Send + Sync + 'static>> {
+    match content_mode {
+        ContentMode::Binary => {
+            let mut headers = OwnedHeaders::new();
+            if let Some(content_type) = broker::content_type(event) {
+                headers = headers.insert(Header { key: "content-type", value: Some(content_type) });
+            }
+            for (name, value) in broker::attributes(event) {
+                headers = headers.insert(Header { key: &format!("ce_{name}"), value: Some(value.as_str()) });
+            }
+            Ok((headers, broker::data(event)?))
+        }
+        ContentMode::Structured => {
+            let headers = OwnedHeaders::new()
+                .insert(Header { key: "content-type", value: Some(STRUCTURED_CONTENT_TYPE) });
+            Ok((headers, binding::structured(event)?))
+        }
+        ContentMode::Batch => Err("batch content mode is not supported by the Kafka binding".into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binding::test_endpoint as endpoint;
+    use crate::binding::test_event as event;
+    use rdkafka::message::Headers;
+
+    #[test]
+    fn message_when_binary_then_ce_headers_and_data_value(){
+        let (headers, value) = message(ContentMode::Binary, &event("1")).unwrap();
+
+        let headers: Vec<(String, Vec<u8>)> = headers.iter()
+            .map(|header| (header.key.to_string(), header.value.unwrap().to_vec()))
+            .collect();
+        assert!(headers.contains(&(String::from("content-type"), b"application/json".to_vec())));
+        assert!(headers.contains(&(String::from("ce_id"), b"1".to_vec())));
+        assert!(headers.contains(&(String::from("ce_subject"), b"xyz".to_vec())));
+        assert_eq!(value, br#"{"subject":{"id":"xyz"}}"#.to_vec());
+    }
+
+    #[test]
+    fn message_when_batch_then_error(){
+        assert!(message(ContentMode::Batch, &event("1")).is_err())
+    }
+
+    /// Needs a local broker, see the Kafka section of the README,
+    /// and is run with `CDEVENT_TEST_KAFKA=localhost:9092 cargo test --features kafka -- --ignored`.
+    #[tokio::test]
+    #[ignore]
+    async fn send_when_local_broker_then_delivered(){
+        let brokers = std::env::var("CDEVENT_TEST_KAFKA").unwrap_or_else(|_| String::from("localhost:9092"));
+        let endpoint = endpoint(format!("kafka://{brokers}/cdevent-test?key=subject"), ContentMode::Binary);
+
+        let delivered = send(&endpoint, &[event("1")]).await;
+
+        assert!(delivered.is_ok(), "{delivered:?}");
+    }
+}
@@ -1,17 +1,46 @@
 mod service;
+#[cfg(feature = "amqp")]
+mod amqp;
 mod args;
+mod auth;
+mod binding;
+#[cfg(any(feature = "amqp", feature = "kafka", feature = "mqtt", feature = "nats"))]
+mod broker;
+mod chain;
+mod ci;
+mod delivery;
+mod endpoint;
+mod exec;
+mod file;
+mod git;
+mod headers;
+mod http;
+mod id;
+#[cfg(feature = "kafka")]
+mod kafka;
+#[cfg(feature = "mqtt")]
+mod mqtt;
+#[cfg(feature = "nats")]
+mod nats;
+mod send;
+mod signing;
+mod spool;
+mod template;
+mod throttle;
+mod timestamp;
+#[cfg(unix)]
+mod unix;
 
 use std::collections::HashMap;
-use std::error::Error;
 use std::ffi::OsString;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::ExitCode;
-use clap::{arg, Command, builder::styling, Arg};
-use cloudevents::{AttributesReader, Data};
-use cloudevents::binding::reqwest::RequestBuilderExt;
+use clap::{arg, Command, builder::styling, Arg, ArgAction, ArgGroup, ArgMatches};
+use cloudevents::{AttributesReader, Data, Event};
 use config::Map;
 use log::debug;
-use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use delivery::EndpointResult;
+use endpoint::{Endpoint, Policy};
 
 // =============================
 // ========= Cli Setup =========
@@ -23,9 +52,14 @@ fn cli() -> Command {
         .literal(styling::AnsiColor::Blue.on_default().bold())
         .placeholder(styling::AnsiColor::Cyan.on_default());
 
-    let default_args: [Arg; 2] = [
-        arg!(-i --id <ID> "The CloudEvent ID of the event").required(true),
-        arg!(-s --source <SOURCE> "The source of the event").required(true),
+    let default_args: [Arg; 4] = [
+        arg!(-i --id <ID> "The CloudEvent ID of the event, generated with --id-strategy when not given"),
+        arg!(--"id-strategy" <STRATEGY> "How the ID is generated when --id is not given, hash derives it from the event content")
+            .value_parser(["uuid4", "uuid7", "ulid", "hash"])
+            .default_value("uuid4"),
+        arg!(-s --source <SOURCE> "The source of the event, required unless taken from the CI system with --ci or from the git remote with --git"),
+        arg!(--timestamp <TIMESTAMP> "When the event happened, as an RFC 3339 date, Unix epoch seconds or milliseconds, or git:<rev> for a commit's date [default: now]")
+            .value_parser(timestamp::parse_timestamp),
     ];
 
     Command::new("cdevent")
@@ -36,14 +70,27 @@ fn cli() -> Command {
         .color(clap::ColorChoice::Auto)
         .styles(STYLES)
         .args([
-            arg!(-e --endpoint <URL> "The endpoint to send events to").required(true),
+            arg!(-e --endpoint <URL> "The endpoint to send events to, can be repeated. Use file:///path or - to write events to a file or stdout").action(ArgAction::Append),
+            arg!(--policy <POLICY> "Whether delivery succeeds when all endpoints or any endpoint accepts the event [default: all]")
+                .value_parser(["all", "any"]),
+            arg!(--"content-mode" <MODE> "How the CloudEvent is encoded in the request [default: binary]")
+                .value_parser(["binary", "structured", "batch"]),
+            arg!(--compress <ENCODING> "Compress HTTP request bodies, sending them uncompressed to endpoints that answer 415 Unsupported Media Type")
+                .value_parser(["gzip", "zstd"]),
             arg!(-q --quiet "Suppress output"),
-            arg!(-c --config <FILE> "The configuration to use for the event"),
+            arg!(-c --config <FILE> "The configuration to use for the event, such as a list of endpoints"),
             arg!(-o --output <FILE> "The file to write the event to"),
             arg!(-f --format <FORMAT> "The format to write the event in"),
             arg!(-v --verbose "Increase verbosity"),
-            arg!(-H --headers <HEADERS> ... "Headers to include in the request").value_parser(parse_headers),
+            arg!(-H --headers <HEADERS> "Headers to include in the request, as 'Name: value' or Name=value,Name=value. Values can use {{...}} event templates")
+                .action(ArgAction::Append)
+                .value_parser(headers::parse_headers),
         ])
+        .args(auth::oauth2_args())
+        .args(signing::hmac_args())
+        .args(spool::spool_args())
+        .args(ci::ci_args())
+        .args(git::git_args())
         // .subcommand(
         //     Command::new("artifact")
         //         .about("An artifact produced by a build")
@@ -117,6 +164,33 @@ fn cli() -> Command {
         //         .subcommand(Command::new("pop").arg(arg!([STASH])))
         //         .subcommand(Command::new("apply").arg(arg!([STASH]))),
         // )
+        .subcommand(
+            Command::new("exec")
+                .about("Run a command, sending a started event before and a finished event after it")
+                // Both events get a generated ID and the current time, so only the source and ID strategy apply.
+                .args(default_args.iter().filter(|arg| ["id-strategy", "source"].contains(&arg.get_id().as_str())).cloned())
+                .args(exec::exec_args())
+                .args(chain::chain_args())
+                .arg(Arg::new("command")
+                    .value_name("COMMAND")
+                    .help("The command to run, with its arguments after --")
+                    .num_args(1..)
+                    .last(true)
+                    .required(true)
+                    .value_parser(clap::value_parser!(OsString)))
+        )
+        .subcommand(
+            Command::new("flush")
+                .about("Retry the events kept in the --spool-dir")
+                .args(throttle::throttle_args())
+        )
+        .subcommand(
+            Command::new("send")
+                .about("Send prebuilt CDEvents or CloudEvents")
+                .args(send::send_args())
+                .args(throttle::throttle_args())
+                .group(ArgGroup::new("input").args(["event", "from-file", "stdin"]).required(true))
+        )
         .subcommand(
             Command::new("service")
                 .about("A service running software in an environment")
@@ -126,7 +200,8 @@ fn cli() -> Command {
                 .subcommand(
                     Command::new("deployed")
                         .args(default_args)
-                        .args(service::deployed_args()))
+                        .args(service::deployed_args())
+                        .args(chain::chain_args()))
                 // .subcommand(Command::new("published").arg(arg!([STASH])))
                 // .subcommand(Command::new("removed").arg(arg!([STASH])))
                 // .subcommand(Command::new("rolledback").arg(arg!([STASH])))
@@ -190,13 +265,11 @@ fn push_args() -> Vec<clap::Arg> {
 async fn main() -> Result<ExitCode, ExitCode> {
     let matches = cli().get_matches();
 
-    let endpoint = matches.get_one::<String>("endpoint");
-    let empty_header: Vec<(String,String)> = Vec::new();
-    let headers:HashMap<String,String> = matches.try_get_one::<Vec<(String,String)>>("headers")
-        .unwrap()
-        .map(|c| c.into_iter().map(move |t| { let x = t.clone(); (x.0, x.1)}).collect())
-        .unwrap_or(HashMap::new());
-    
+    let spool_dir = spool::spool_parse(&matches);
+    let ci = ci::ci_parse(&matches)
+        .map_err(|e| { eprintln!("{e}"); ExitCode::FAILURE })?;
+    let ci = ci::with_git(ci, git::git_parse(&matches));
+
     match matches.subcommand() {
         // Some(("clone", sub_matches)) => {
         //     println!(
@@ -240,13 +313,76 @@ async fn main() -> Result<ExitCode, ExitCode> {
         //         .collect::<Vec<_>>();
         //     println!("Adding {paths:?}");
         // }
+        Some(("exec", sub_matches)) => {
+            let (endpoints, policy) = endpoints_parse(&matches)?;
+            let args = exec::exec_parse(sub_matches, ci.as_ref())
+                .map_err(|e| { eprintln!("Invalid arguments: {e}"); ExitCode::FAILURE })?;
+            let chain = chain::chain_parse(sub_matches);
+            // Endpoints that fail are reported, and spooled with --spool-dir, but the exit code is always the command's.
+            // The finished event follows the started event in the chain.
+            let mut finished_chain = chain.clone();
+            match exec::started_event(&args, &chain) {
+                Ok(event) => {
+                    post(&endpoints, &event, policy, spool_dir.as_deref()).await;
+                    finished_chain = chain.followed_by(event.id());
+                }
+                Err(e) => eprintln!("Could not build the started event: {e}"),
+            }
+            let finished = exec::run(&args).await;
+            match exec::finished_event(&args, &finished, &finished_chain) {
+                Ok(event) => {
+                    post(&endpoints, &event, policy, spool_dir.as_deref()).await;
+                    if let Err(e) = chain::handoff(&chain, &event) {
+                        eprintln!("Could not write the chain to the --chain-env-file: {e}");
+                    }
+                }
+                Err(e) => eprintln!("Could not build the finished event: {e}"),
+            }
+            return Ok(finished.exit_code());
+        }
+        Some(("flush", sub_matches)) => {
+            let Some(dir) = &spool_dir else {
+                eprintln!("cdevent flush needs --spool-dir");
+                return Err(ExitCode::FAILURE);
+            };
+            let (endpoints, _) = endpoints_parse(&matches)?;
+            let remaining = spool::flush(dir, &endpoints, throttle::throttle_parse(sub_matches)).await
+                .map_err(|e| { eprintln!("Could not flush {}: {e}", dir.display()); ExitCode::FAILURE })?;
+            if remaining > 0 {
+                eprintln!("{remaining} event(s) are still spooled in {}", dir.display());
+                return Err(ExitCode::FAILURE);
+            }
+        }
+        Some(("send", sub_matches)) => {
+            let (endpoints, policy) = endpoints_parse(&matches)?;
+            let args = send::send_parse(sub_matches);
+            let events = send::read_events(&args.input)
+                .map_err(|e| { eprintln!("Invalid events:\n{e}"); ExitCode::FAILURE })?;
+            eprintln!("Sending {} event(s) to {} endpoint(s)", events.len(), endpoints.len());
+            let sent = send::send(&endpoints, events, args.throttle).await;
+            let mut failed = 0;
+            for sent in &sent {
+                send::report(sent);
+                if !settle(&sent.event, &sent.results, policy, spool_dir.as_deref()) {
+                    failed += 1;
+                }
+            }
+            if failed > 0 {
+                eprintln!("{failed} of {} event(s) were not delivered", sent.len());
+                return Err(ExitCode::FAILURE);
+            }
+        }
         Some(("service", sub_matches)) => {
             let service_command = sub_matches.subcommand().unwrap_or(("help", sub_matches));
             match service_command {
                 ("deployed", sub_matches) => {
-                    let args = service::deployed_parse(sub_matches);
+                    let (endpoints, policy) = endpoints_parse(&matches)?;
+                    let args = service::deployed_parse(sub_matches, ci.as_ref())
+                        .map_err(|e| { eprintln!("Invalid arguments: {e}"); ExitCode::FAILURE })?;
+                    let chain = chain::chain_parse(sub_matches);
                     // let cd_event: CDEvent = CDEvent::from(args.clone());
-                    let cloud_event = service::to_cloud_event(&args);
+                    let cloud_event = service::to_cloud_event(&args, &chain)
+                        .map_err(|e| { eprintln!("Could not build the event: {e}"); ExitCode::FAILURE })?;
                     // let custom_data = get_custom_data(&cloud_event);
                     // match custom_data {
                     //     Some(data) => {
@@ -256,21 +392,12 @@ async fn main() -> Result<ExitCode, ExitCode> {
                     //         println!("Event {}: Deployed service {} to environment {} with no custom data", &cloud_event.id(), &cloud_event.subject().unwrap(), args.env_id);
                     //     }
                     // }
-                    let id = cloud_event.id();
-                    let sub = cloud_event.subject().unwrap();
-                    println!("Posting to endpoint: {endpoint:?}, id: {id:?}, subject: {sub:?}");
-                    let httpHeaders: HeaderMap = HeaderMap::from_iter(headers.iter().map(|(k,v)| (HeaderName::try_from(k.as_str()).unwrap(), HeaderValue::from_str(v.as_str()).unwrap())));
-                    let response = reqwest::Client::new()
-                        .post(endpoint.unwrap())
-                        .event(cloud_event)
-                        .map_err(|e| ExitCode::FAILURE)?
-                        .header("Access-Control-Allow-Origin", "*")
-                        .headers(httpHeaders)
-                        .send()
-                        .await
-                        .map_err(|e| ExitCode::FAILURE)?;
-
-                    println!("Response: {:?}", response);
+                    if !post(&endpoints, &cloud_event, policy, spool_dir.as_deref()).await {
+                        return Err(ExitCode::FAILURE);
+                    }
+                    if let Err(e) = chain::handoff(&chain, &cloud_event) {
+                        eprintln!("Could not write the chain to the --chain-env-file: {e}");
+                    }
                 }
                 ("created", sub_matches) => {
                     println!("Created");
@@ -299,6 +426,41 @@ async fn main() -> Result<ExitCode, ExitCode> {
     Ok(ExitCode::SUCCESS)
 }
 
+/// The endpoints to send to, parsed only by the subcommands that send events.
+fn endpoints_parse(matches: &ArgMatches) -> Result<(Vec<Endpoint>, Policy), ExitCode> {
+    endpoint::endpoints_parse(matches)
+        .map_err(|e| { eprintln!("Invalid endpoint configuration: {e}"); ExitCode::FAILURE })
+}
+
+/// Delivers the event to the endpoints and reports the results, returning whether it was delivered or spooled, see [`settle`].
+async fn post(endpoints: &[Endpoint], event: &Event, policy: Policy, spool_dir: Option<&Path>) -> bool {
+    let id = event.id();
+    let sub = event.subject().unwrap_or_default();
+    eprintln!("Posting to {} endpoint(s), id: {id:?}, subject: {sub:?}", endpoints.len());
+    let results = delivery::deliver(&reqwest::Client::new(), endpoints, std::slice::from_ref(event)).await;
+    delivery::report(&results);
+    settle(event, &results, policy, spool_dir)
+}
+
+/// Spools the event for the endpoints that failed when there is a spool directory,
+/// returning whether the event was delivered according to the policy or kept for `cdevent flush`.
+fn settle(event: &Event, results: &[EndpointResult], policy: Policy, spool_dir: Option<&Path>) -> bool {
+    if let Some(dir) = spool_dir {
+        match spool::spool_failed(dir, event, results) {
+            Ok(Some(path)) => {
+                eprintln!("Spooled event {} to {} for `cdevent flush`", event.id(), path.display());
+                return true;
+            }
+            Ok(None) => {}
+            Err(e) => {
+                eprintln!("Could not spool event {}: {e}", event.id());
+                return false;
+            }
+        }
+    }
+    policy.is_satisfied(delivery::succeeded(results), results.len())
+}
+
 fn get_custom_data(event: &cloudevents::Event) -> Option<HashMap<String, String>> {
     debug!("Event: {:?}", event);
     event.data().and_then(|data| {
@@ -334,8 +496,3 @@ fn get_custom_data(event: &cloudevents::Event) -> Option<HashMap<String, String>
         }
     })
 }
-
-fn parse_headers(s: &str) -> Result<Vec<(String, String)>, Box<dyn Error + Send + Sync + 'static>>
-{
-    args::parse_comma_delimited(s, "No headers provided")
-}
\ No newline at end of file
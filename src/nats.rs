@@ -0,0 +1,97 @@
+use std::error::Error;
+use async_nats::HeaderMap;
+use cloudevents::Event;
+use crate::binding::{self, STRUCTURED_CONTENT_TYPE};
+use crate::broker;
+use crate::endpoint::{ContentMode, Endpoint};
+
+/// Publishes the events to a `nats://host:port/subject` endpoint following the
+/// [NATS protocol binding](https://github.com/cloudevents/spec/blob/v1.0.2/cloudevents/bindings/nats-protocol-binding.md).
+///
+/// With `?jetstream=true` each publish waits for the JetStream acknowledgement instead of only flushing to the server.
+pub async fn send(endpoint: &Endpoint, events: &[Event]) -> Result<String, Box<dyn Error + Send + Sync + 'static>> {
+    let target = endpoint.target();
+    if target.path.is_empty() {
+        return Err("no subject given, expected nats://host/subject".into());
+    }
+    let client = async_nats::connect(format!("nats://{}", target.host)).await?;
+
+    if target.param("jetstream") == Some("true") {
+        let jetstream = async_nats::jetstream::new(client);
+        let mut acknowledged = String::new();
+        for event in events {
+            let (headers, payload) = message(endpoint.content_mode, event)?;
+            let ack = jetstream.publish_with_headers(target.path.clone(), headers, payload.into())
+                .await?
+                .await?;
+            acknowledged = format!("stream {}, sequence {}", ack.stream, ack.sequence);
+        }
+        return Ok(acknowledged);
+    }
+
+    for event in events {
+        let (headers, payload) = message(endpoint.content_mode, event)?;
+        client.publish_with_headers(target.path.clone(), headers, payload.into()).await?;
+    }
+    client.flush().await?;
+    Ok(String::from("published"))
+}
+
+/// Headers and payload of the NATS message for the event.
+fn message(content_mode: ContentMode, event: &Event) -> Result<(HeaderMap, Vec<u8>), Box<dyn Error + Send + Sync + 'static>> {
+    let mut headers = HeaderMap::new();
+    match content_mode {
+        ContentMode::Binary => {
+            if let Some(content_type) = broker::content_type(event) {
+                headers.insert("content-type", content_type);
+            }
+            for (name, value) in broker::attributes(event) {
+                headers.insert(format!("ce-{name}").as_str(), value.as_str());
+            }
+            Ok((headers, broker::data(event)?))
+        }
+        ContentMode::Structured => {
+            headers.insert("content-type", STRUCTURED_CONTENT_TYPE);
+            Ok((headers, binding::structured(event)?))
+        }
+        ContentMode::Batch => Err("batch content mode is not supported by the NATS binding".into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binding::test_endpoint as endpoint;
+    use crate::binding::test_event as event;
+
+    #[test]
+    fn message_when_binary_then_ce_headers_and_data_payload(){
+        let (headers, payload) = message(ContentMode::Binary, &event("1")).unwrap();
+
+        assert_eq!(headers.get("ce-id").unwrap().as_str(), "1");
+        assert_eq!(headers.get("ce-subject").unwrap().as_str(), "xyz");
+        assert_eq!(headers.get("content-type").unwrap().as_str(), "application/json");
+        assert_eq!(payload, br#"{"subject":{"id":"xyz"}}"#.to_vec());
+    }
+
+    #[test]
+    fn message_when_structured_then_cloudevent_payload(){
+        let (headers, payload) = message(ContentMode::Structured, &event("1")).unwrap();
+
+        assert_eq!(headers.get("content-type").unwrap().as_str(), STRUCTURED_CONTENT_TYPE);
+        assert_eq!(payload, serde_json::to_vec(&event("1")).unwrap());
+    }
+
+    /// Needs a local server, eg. `nats-server -js`,
+    /// and is run with `cargo test --features nats -- --ignored`.
+    #[tokio::test]
+    #[ignore]
+    async fn send_when_local_server_then_published(){
+        let host = std::env::var("CDEVENT_TEST_NATS").unwrap_or_else(|_| String::from("localhost:4222"));
+        let endpoint = endpoint(format!("nats://{host}/cdevent.test"), ContentMode::Binary);
+
+        let published = send(&endpoint, &[event("1")]).await;
+
+        assert!(published.is_ok(), "{published:?}");
+    }
+}
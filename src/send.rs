@@ -0,0 +1,260 @@
+use std::error::Error;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use cdevents_sdk::CDEvent;
+use clap::{arg, Arg, ArgMatches};
+use cloudevents::{AttributesReader, Event};
+use serde_json::Value;
+use tokio::task::JoinSet;
+use crate::delivery::{self, EndpointResult};
+use crate::endpoint::{ContentMode, Endpoint};
+use crate::throttle::{self, Throttle, ThrottleArgs};
+
+// ========= Send =========
+pub enum Input {
+    File(PathBuf),
+    Stdin,
+    /// A single CDEvent document.
+    Event(PathBuf),
+}
+
+pub struct SendArgs {
+    pub input: Input,
+    pub throttle: ThrottleArgs,
+}
+
+/// The results of sending one event to every endpoint.
+pub struct EventResult {
+    pub event: Event,
+    pub results: Vec<EndpointResult>,
+}
+
+pub fn send_args() -> [Arg; 3] {
+    [
+        arg!(--event <FILE> "A CDEvent JSON document to send"),
+        arg!(--"from-file" <FILE> "Newline delimited JSON, or a JSON array, of CDEvents or CloudEvents to send"),
+        arg!(--stdin "Read the events from stdin"),
+    ]
+}
+
+pub fn send_parse(matches: &ArgMatches) -> SendArgs {
+    let input = if let Some(path) = matches.get_one::<String>("event") {
+        Input::Event(PathBuf::from(path))
+    } else if let Some(path) = matches.get_one::<String>("from-file") {
+        Input::File(PathBuf::from(path))
+    } else {
+        Input::Stdin
+    };
+    let throttle = throttle::throttle_parse(matches);
+    SendArgs {
+        input,
+        throttle
+    }
+}
+
+/// Reads and validates every event before any is sent, so a bad line never leaves a stream half delivered.
+pub fn read_events(input: &Input) -> Result<Vec<Event>, Box<dyn Error + Send + Sync + 'static>> {
+    match input {
+        Input::File(path) => parse_events(&read_file(path)?),
+        Input::Stdin => {
+            let mut contents = String::new();
+            std::io::stdin().read_to_string(&mut contents)?;
+            parse_events(&contents)
+        }
+        Input::Event(path) => {
+            let cd_event: CDEvent = serde_json::from_str(&read_file(path)?)
+                .map_err(|e| format!("{} is not a valid CDEvent: {e}", path.display()))?;
+            Ok(vec![cd_event.try_into()?])
+        }
+    }
+}
+
+fn read_file(path: &Path) -> Result<String, Box<dyn Error + Send + Sync + 'static>> {
+    std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()).into())
+}
+
+/// Parses a JSON array, or newline delimited JSON, where each value is a CloudEvent when it has a `specversion` and a CDEvent otherwise.
+pub fn parse_events(contents: &str) -> Result<Vec<Event>, Box<dyn Error + Send + Sync + 'static>> {
+    let values: Vec<(String, Result<Value, serde_json::Error>)> = if contents.trim_start().starts_with('[') {
+        serde_json::from_str::<Vec<Value>>(contents)?
+            .into_iter()
+            .enumerate()
+            .map(|(index, value)| (format!("event {}", index + 1), Ok(value)))
+            .collect()
+    } else {
+        contents.lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(index, line)| (format!("line {}", index + 1), serde_json::from_str(line)))
+            .collect()
+    };
+
+    let mut events = Vec::with_capacity(values.len());
+    let mut errors = Vec::new();
+    for (position, value) in values {
+        match value.map_err(Into::into).and_then(to_event) {
+            Ok(event) => events.push(event),
+            Err(e) => errors.push(format!("{position}: {e}")),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors.join("\n").into());
+    }
+    if events.is_empty() {
+        return Err("no events to send".into());
+    }
+    Ok(events)
+}
+
+fn to_event(value: Value) -> Result<Event, Box<dyn Error + Send + Sync + 'static>> {
+    if value.get("specversion").is_some() {
+        return Ok(serde_json::from_value(value)?);
+    }
+    let cd_event: CDEvent = serde_json::from_value(value)?;
+    Ok(cd_event.try_into()?)
+}
+
+/// Sends the events to the endpoints within the throttle's limits, returning the results in the order of the events.
+///
+/// Batch content mode endpoints are sent every event in one request, and its result counts for each event.
+pub async fn send(endpoints: &[Endpoint], events: Vec<Event>, throttle: ThrottleArgs) -> Vec<EventResult> {
+    let (batch, single): (Vec<Endpoint>, Vec<Endpoint>) = endpoints.iter()
+        .cloned()
+        .partition(|endpoint| endpoint.content_mode == ContentMode::Batch);
+    let client = reqwest::Client::new();
+    let batch_results = if batch.is_empty() {
+        Vec::new()
+    } else {
+        delivery::deliver(&client, &batch, &events).await
+    };
+
+    let single = Arc::new(single);
+    let mut throttle = Throttle::new(throttle);
+    let mut tasks = JoinSet::new();
+    for (index, event) in events.into_iter().enumerate() {
+        let permit = throttle.acquire().await;
+        let endpoints = single.clone();
+        // The client is a handle to one shared connection pool.
+        let client = client.clone();
+        tasks.spawn(async move {
+            let results = delivery::deliver(&client, &endpoints, std::slice::from_ref(&event)).await;
+            drop(permit);
+            (index, event, results)
+        });
+    }
+
+    let mut sent = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        sent.push(joined.expect("delivering an event panicked"));
+    }
+    sent.sort_by_key(|(index, _, _)| *index);
+    sent.into_iter()
+        .map(|(_, event, mut results)| {
+            results.extend(batch_results.iter().cloned());
+            EventResult { event, results }
+        })
+        .collect()
+}
+
+pub fn report(sent: &EventResult) {
+    eprintln!("Event {}:", sent.event.id());
+    delivery::report(&sent.results);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binding::test_endpoint as endpoint;
+    use crate::binding::test_event as event;
+
+    fn ndjson(events: &[Event]) -> String {
+        events.iter().map(|event| serde_json::to_string(event).unwrap() + "\n").collect()
+    }
+
+    #[test]
+    fn parse_events_when_ndjson_cloudevents_then_events(){
+        let contents = ndjson(&[event("1"), event("2")]) + "\n";
+
+        assert_eq!(parse_events(&contents).unwrap(), vec![event("1"), event("2")]);
+    }
+
+    #[test]
+    fn parse_events_when_json_array_then_events(){
+        let contents = serde_json::to_string(&[event("1"), event("2")]).unwrap();
+
+        assert_eq!(parse_events(&contents).unwrap(), vec![event("1"), event("2")]);
+    }
+
+    #[test]
+    fn parse_events_when_invalid_line_then_error_with_line_number(){
+        let contents = ndjson(&[event("1")]) + "{not json\n";
+
+        let error = parse_events(&contents).unwrap_err().to_string();
+
+        assert!(error.starts_with("line 2: "), "{error}");
+    }
+
+    #[test]
+    fn parse_events_when_empty_then_error(){
+        assert!(parse_events("\n").is_err())
+    }
+
+    #[test]
+    fn read_events_when_event_is_not_a_cdevent_then_error(){
+        let path = std::env::temp_dir().join(format!("cdevent-test-{}-event.json", std::process::id()));
+        std::fs::write(&path, r#"{"specversion":"1.0","id":"1"}"#).unwrap();
+
+        let error = read_events(&Input::Event(path.clone())).unwrap_err().to_string();
+
+        assert!(error.starts_with(&format!("{} is not a valid CDEvent", path.display())), "{error}");
+    }
+
+    #[test]
+    fn read_events_when_event_is_a_cdevent_then_cloudevent(){
+        let path = std::env::temp_dir().join(format!("cdevent-test-{}-cdevent.json", std::process::id()));
+        std::fs::write(&path, r#"{
+            "context": {
+                "version": "0.3.0",
+                "id": "271069a8-fc18-44f1-b38f-9d70a1695819",
+                "source": "/event/source/123",
+                "type": "dev.cdevents.service.deployed.0.1.1",
+                "timestamp": "2023-03-20T14:27:05.315384Z"
+            },
+            "subject": {
+                "id": "mySubject123",
+                "source": "/event/source/123",
+                "type": "service",
+                "content": {
+                    "environment": {"id": "test123"},
+                    "artifactId": "pkg:oci/myapp@sha256%3A0b31b1c02ff458ad9b7b81cbdf8f028bd54699fa151f221d1e8de6817db93427"
+                }
+            }
+        }"#).unwrap();
+
+        let events = read_events(&Input::Event(path)).unwrap();
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].ty(), "dev.cdevents.service.deployed.0.1.1");
+        assert_eq!(events[0].source(), "/event/source/123");
+        assert_eq!(events[0].id(), "271069a8-fc18-44f1-b38f-9d70a1695819");
+    }
+
+    #[tokio::test]
+    async fn send_when_batch_and_structured_endpoints_then_result_per_event_and_endpoint(){
+        let mut server = mockito::Server::new_async().await;
+        let batch = server.mock("POST", "/batch").expect(1).create_async().await;
+        let single = server.mock("POST", "/single").expect(2).create_async().await;
+        let endpoints = [
+            endpoint(format!("{}/batch", server.url()), ContentMode::Batch),
+            endpoint(format!("{}/single", server.url()), ContentMode::Structured),
+        ];
+
+        let sent = send(&endpoints, vec![event("1"), event("2")], ThrottleArgs { concurrency: 2, max_rps: None }).await;
+
+        assert_eq!(sent.iter().map(|sent| sent.event.id()).collect::<Vec<_>>(), vec!["1", "2"]);
+        assert!(sent.iter().all(|sent| sent.results.len() == 2 && delivery::succeeded(&sent.results) == 2));
+        batch.assert_async().await;
+        single.assert_async().await;
+    }
+}
@@ -3,19 +3,25 @@ use std::error::Error;
 use cdevents_sdk::{CDEvent, Subject, service_deployed_0_1_1};
 use clap::{arg, Arg, ArgMatches};
 use serde_json::{to_value};
+use time::OffsetDateTime;
 use crate::args;
+use crate::chain::{self, ChainArgs};
+use crate::ci::{self, CiMetadata};
+use crate::id::{self, IdStrategy};
 
 // ========= Service Deployed =========
 #[derive(Clone)]
 pub struct ServiceDeployedArgs {
-    pub id: String,
+    pub id: Option<String>,
+    pub id_strategy: IdStrategy,
     pub source: String,
     pub subject_id: String,
     pub env_id: String,
     pub env_name: Option<String>,
     pub env_source: Option<String>,
     pub artifact: Option<String>,
-    pub custom_data: Option<HashMap<String,String>>
+    pub custom_data: Option<HashMap<String,String>>,
+    pub timestamp: Option<OffsetDateTime>,
 }
 
 impl From<ServiceDeployedArgs> for CDEvent {
@@ -31,20 +37,21 @@ impl From<ServiceDeployedArgs> for CDEvent {
                 .with_id(args.subject_id.try_into().unwrap())
                 .with_source(args.source.clone().try_into().unwrap())
         )
-            .with_id(args.id.try_into().unwrap())
             .with_source(args.source.try_into().unwrap());
 
         if let Some(custom_data) = args.custom_data {
             cd_event = cd_event.with_custom_data(to_value(custom_data).unwrap());
         }
-
+        if let Some(timestamp) = args.timestamp {
+            cd_event = cd_event.with_timestamp(timestamp);
+        }
         cd_event
     }
 }
 pub fn deployed_args() -> [Arg; 6] {
     [
         arg!(--subid <SUBJECT_ID> "The unique ID or name of the service").required(true),
-        arg!(--envid <ENVIRONMENT_ID> "The unique environment ID").required(true),
+        arg!(--envid <ENVIRONMENT_ID> "The unique environment ID, required unless the CI job has an environment"),
         arg!(--envname <ENVIRONMENT_NAME> "The name of the environment eg. prod"),
         arg!(--envsource <ENVIRONMENT_SOURCE> "The source of the environment"),
         arg!(--artifact <ARTIFACT_ID> "Identifier of the artifact deployed with this service").required(true),
@@ -57,30 +64,46 @@ fn parse_custom_data(s: &str) -> Result<Vec<(String, String)>, Box<dyn Error + S
     args::parse_comma_delimited(s, "No custom data provided")
 }
 
-pub fn deployed_parse(matches: &ArgMatches) -> ServiceDeployedArgs {
-    let id = matches.get_one::<String>("id").unwrap().into();
-    let source = matches.get_one::<String>("source").unwrap().into();
+/// Parses the arguments, taking the source, environment and custom data from the CI run where they are not given.
+pub fn deployed_parse(matches: &ArgMatches, ci: Option<&CiMetadata>) -> Result<ServiceDeployedArgs, Box<dyn Error + Send + Sync + 'static>> {
+    let id = matches.get_one::<String>("id").cloned();
+    let id_strategy = IdStrategy::parse(matches.get_one::<String>("id-strategy").unwrap());
+    let source = ci::source(matches.get_one::<String>("source"), ci)?;
     let subject_id = matches.get_one::<String>("subid").unwrap().into();
-    let env_id = matches.get_one::<String>("envid").unwrap().into();
-    let env_name = matches.try_get_one::<String>("envname").unwrap().cloned();
-    let env_source = matches.try_get_one("envsource").unwrap().cloned();
+    let ci_environment = ci.and_then(|ci| ci.environment.as_ref());
+    let env_id = match matches.get_one::<String>("envid") {
+        Some(env_id) => env_id.clone(),
+        None => ci_environment.map(|environment| environment.id.clone())
+            .ok_or("--envid is required when it cannot be taken from the CI job's environment")?,
+    };
+    let env_name = matches.try_get_one::<String>("envname").unwrap().cloned()
+        .or_else(|| ci_environment.and_then(|environment| environment.name.clone()));
+    let env_source = matches.try_get_one::<String>("envsource").unwrap().cloned()
+        .or_else(|| ci_environment.and_then(|environment| environment.source.clone()));
     let artifact = matches.try_get_one("artifact").unwrap().cloned();
     let custom_data:Option<HashMap<String,String>> = matches.try_get_one::<Vec<(String,String)>>("custom")
         .unwrap()
         .map(|c| c.into_iter().map(move |t| { let x = t.clone(); (x.0, x.1)}).collect());
-    ServiceDeployedArgs {
+    let custom_data = ci::custom_data(custom_data, ci);
+    let timestamp = matches.get_one::<OffsetDateTime>("timestamp").copied();
+    Ok(ServiceDeployedArgs {
         id,
+        id_strategy,
         source,
         subject_id,
         env_id,
         env_name,
         env_source,
         artifact,
-        custom_data
-    }
+        custom_data,
+        timestamp
+    })
 }
 
-pub fn to_cloud_event(args: &ServiceDeployedArgs) -> cloudevents::Event {
+/// The CloudEvent of the deployment in the chain, with the `--id` or one generated for it.
+pub fn to_cloud_event(args: &ServiceDeployedArgs, chain: &ChainArgs) -> Result<cloudevents::Event, Box<dyn Error + Send + Sync + 'static>> {
     let cd_event:CDEvent = CDEvent::from(args.clone());
-    cd_event.clone().try_into().unwrap()
+    chain::to_cloud_event(cd_event, chain, |document| {
+        args.id.clone().unwrap_or_else(|| id::generate(args.id_strategy, document, args.timestamp.is_some()))
+    })
 }
@@ -0,0 +1,107 @@
+use std::error::Error;
+use clap::{arg, Arg, ArgMatches};
+use hmac::{Hmac, Mac};
+use reqwest::header::{HeaderName, HeaderValue};
+use serde::Deserialize;
+use sha2::Sha256;
+
+// ========= HMAC Signing =========
+#[derive(Clone, Debug, Deserialize)]
+pub struct HmacArgs {
+    pub secret: String,
+    #[serde(default = "default_header")]
+    pub header: String,
+    #[serde(default = "default_prefix")]
+    pub prefix: String,
+}
+
+fn default_header() -> String {
+    String::from("X-Signature-256")
+}
+
+fn default_prefix() -> String {
+    String::from("sha256=")
+}
+
+pub fn hmac_args() -> [Arg; 3] {
+    [
+        arg!(--"sign-hmac-secret" <SECRET> "Secret used to sign the request body with HMAC-SHA256")
+            .env("CDEVENT_SIGN_HMAC_SECRET")
+            .hide_env_values(true),
+        arg!(--"sign-hmac-header" <HEADER> "The header the signature is sent in")
+            .default_value("X-Signature-256")
+            .value_parser(parse_header_name),
+        arg!(--"sign-hmac-prefix" <PREFIX> "Text placed before the hex encoded signature")
+            .default_value("sha256="),
+    ]
+}
+
+fn parse_header_name(s: &str) -> Result<String, Box<dyn Error + Send + Sync + 'static>>
+{
+    HeaderName::try_from(s)?;
+    Ok(s.to_string())
+}
+
+pub fn hmac_parse(matches: &ArgMatches) -> Option<HmacArgs> {
+    let secret = matches.get_one::<String>("sign-hmac-secret")?.into();
+    let header = matches.get_one::<String>("sign-hmac-header").unwrap().into();
+    let prefix = matches.get_one::<String>("sign-hmac-prefix").unwrap().into();
+    Some(HmacArgs {
+        secret,
+        header,
+        prefix
+    })
+}
+
+/// Hex encoded HMAC-SHA256 of `body`.
+pub fn sign(secret: &[u8], body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Adds the signature header to a request that is ready to send, computed over the exact body bytes it will carry.
+pub fn sign_request(args: &HmacArgs, request: &mut reqwest::Request) -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
+    let body = match request.body() {
+        Some(body) => body.as_bytes().ok_or("cannot sign a streaming request body")?,
+        None => &[],
+    };
+    let signature = format!("{}{}", args.prefix, sign(args.secret.as_bytes(), body));
+    request.headers_mut().insert(
+        HeaderName::try_from(args.header.as_str())?,
+        HeaderValue::from_str(&signature)?,
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sign_when_rfc4231_test_case_then_expected_digest(){
+        let signature = sign(b"Jefe", b"what do ya want for nothing?");
+        assert_eq!(signature, "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843")
+    }
+
+    #[test]
+    fn sign_request_when_body_then_prefixed_signature_header(){
+        let args = HmacArgs {
+            secret: String::from("Jefe"),
+            header: String::from("X-Hub-Signature-256"),
+            prefix: String::from("sha256="),
+        };
+        let mut request = reqwest::Client::new()
+            .post("http://localhost/events")
+            .body("what do ya want for nothing?")
+            .build()
+            .unwrap();
+
+        sign_request(&args, &mut request).unwrap();
+
+        assert_eq!(
+            request.headers().get("X-Hub-Signature-256").unwrap(),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        )
+    }
+}
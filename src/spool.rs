@@ -0,0 +1,229 @@
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use clap::{arg, Arg, ArgMatches};
+use cloudevents::{AttributesReader, Event};
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinSet;
+use crate::delivery::{self, EndpointResult};
+use crate::endpoint::Endpoint;
+use crate::throttle::{Throttle, ThrottleArgs};
+
+// ========= Offline Spool =========
+/// An event that could not be delivered, with the URLs of the endpoints it is still owed to.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct SpooledEvent {
+    pub event: Event,
+    pub endpoints: Vec<String>,
+}
+
+pub fn spool_args() -> [Arg; 1] {
+    [
+        arg!(--"spool-dir" <DIR> "Directory events are kept in when an endpoint fails, to be retried with `cdevent flush`")
+            .env("CDEVENT_SPOOL_DIR"),
+    ]
+}
+
+pub fn spool_parse(matches: &ArgMatches) -> Option<PathBuf> {
+    matches.get_one::<String>("spool-dir").map(PathBuf::from)
+}
+
+/// Keeps the event for the endpoints that failed, returning the spool file when there were any.
+pub fn spool_failed(dir: &Path, event: &Event, results: &[EndpointResult]) -> Result<Option<PathBuf>, Box<dyn Error + Send + Sync + 'static>> {
+    let failed: Vec<String> = results.iter()
+        .filter(|result| result.outcome.is_err())
+        .map(|result| result.url.clone())
+        .collect();
+    if failed.is_empty() {
+        return Ok(None);
+    }
+    store(dir, event, failed).map(Some)
+}
+
+/// Writes the event to its spool file, adding the endpoints to those it is already owed to when the event was spooled before.
+pub fn store(dir: &Path, event: &Event, endpoints: Vec<String>) -> Result<PathBuf, Box<dyn Error + Send + Sync + 'static>> {
+    fs::create_dir_all(dir)?;
+    let path = dir.join(file_name(event.id()));
+    let mut spooled = match fs::read(&path) {
+        Ok(contents) => serde_json::from_slice(&contents)?,
+        Err(_) => SpooledEvent { event: event.clone(), endpoints: Vec::new() },
+    };
+    for url in endpoints {
+        if !spooled.endpoints.contains(&url) {
+            spooled.endpoints.push(url);
+        }
+    }
+    write(&path, &spooled)?;
+    Ok(path)
+}
+
+/// Retries every spooled event against the endpoints it is still owed to, returning how many events remain spooled.
+///
+/// Endpoints are looked up by URL in the current configuration, so the same `-e` and `--config` arguments should be given as when the event was sent.
+/// An endpoint that accepts the event is removed from its spool file straight away, so it is not sent the event again by a later flush,
+/// and the file is deleted once no endpoint is left. Retries keep the CloudEvent `id`, which receivers can use to drop duplicates.
+pub async fn flush(dir: &Path, endpoints: &[Endpoint], throttle: ThrottleArgs) -> Result<usize, Box<dyn Error + Send + Sync + 'static>> {
+    let endpoints = Arc::new(endpoints.to_vec());
+    let client = reqwest::Client::new();
+    let mut throttle = Throttle::new(throttle);
+    let mut tasks = JoinSet::new();
+    for path in spool_files(dir)? {
+        let permit = throttle.acquire().await;
+        let endpoints = endpoints.clone();
+        let client = client.clone();
+        tasks.spawn(async move {
+            let flushed = flush_file(&client, &path, &endpoints).await;
+            drop(permit);
+            (path, flushed)
+        });
+    }
+
+    let mut remaining = 0;
+    while let Some(joined) = tasks.join_next().await {
+        match joined.expect("flushing an event panicked") {
+            (_, Ok(true)) => {}
+            (_, Ok(false)) => remaining += 1,
+            (path, Err(e)) => {
+                eprintln!("Could not flush {}: {e}", path.display());
+                remaining += 1;
+            }
+        }
+    }
+    Ok(remaining)
+}
+
+/// Retries one spooled event, returning whether every endpoint has now accepted it.
+async fn flush_file(client: &reqwest::Client, path: &Path, endpoints: &[Endpoint]) -> Result<bool, Box<dyn Error + Send + Sync + 'static>> {
+    let mut spooled: SpooledEvent = serde_json::from_slice(&fs::read(path)?)?;
+    let (configured, unknown): (Vec<String>, Vec<String>) = spooled.endpoints.iter()
+        .cloned()
+        .partition(|url| endpoints.iter().any(|endpoint| &endpoint.url == url));
+    for url in &unknown {
+        eprintln!("Event {}: endpoint {url} is not configured, keeping it spooled", spooled.event.id());
+    }
+    let retry: Vec<Endpoint> = endpoints.iter()
+        .filter(|endpoint| configured.contains(&endpoint.url))
+        .cloned()
+        .collect();
+
+    let mut results = Vec::new();
+    if !retry.is_empty() {
+        eprintln!("Flushing event {} to {} endpoint(s)", spooled.event.id(), retry.len());
+        results = delivery::deliver(client, &retry, std::slice::from_ref(&spooled.event)).await;
+        delivery::report(&results);
+    }
+    spooled.endpoints = unknown.into_iter()
+        .chain(results.into_iter().filter(|result| result.outcome.is_err()).map(|result| result.url))
+        .collect();
+
+    if spooled.endpoints.is_empty() {
+        fs::remove_file(path)?;
+        Ok(true)
+    } else {
+        write(path, &spooled)?;
+        Ok(false)
+    }
+}
+
+/// The spool files, oldest first so events are redelivered in the order they happened when `--concurrency` is 1.
+fn spool_files(dir: &Path) -> Result<Vec<PathBuf>, Box<dyn Error + Send + Sync + 'static>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.path().extension().is_some_and(|extension| extension == "json") {
+            files.push((entry.metadata()?.modified()?, entry.path()));
+        }
+    }
+    files.sort();
+    Ok(files.into_iter().map(|(_, path)| path).collect())
+}
+
+/// The event id, with anything that is not safe in a file name percent encoded.
+fn file_name(id: &str) -> String {
+    let mut name = String::with_capacity(id.len() + 5);
+    for byte in id.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.') {
+            name.push(byte as char);
+        } else {
+            name.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    name.push_str(".json");
+    name
+}
+
+/// Replaces the spool file in one step, so an interrupted write never leaves half an event behind.
+fn write(path: &Path, spooled: &SpooledEvent) -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, serde_json::to_vec(spooled)?)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binding::test_endpoint as endpoint;
+    use crate::binding::test_event as event;
+    use crate::endpoint::ContentMode;
+
+    fn spool_dir(test_name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("cdevent-test-{}-spool-{test_name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn throttle() -> ThrottleArgs {
+        ThrottleArgs { concurrency: 4, max_rps: None }
+    }
+
+    #[test]
+    fn file_name_when_unsafe_characters_then_encoded(){
+        assert_eq!(file_name("a/b c"), "a%2Fb%20c.json")
+    }
+
+    #[test]
+    fn store_when_spooled_twice_then_endpoints_merged(){
+        let dir = spool_dir("merge");
+
+        store(&dir, &event("1"), vec![String::from("https://a")]).unwrap();
+        let path = store(&dir, &event("1"), vec![String::from("https://a"), String::from("https://b")]).unwrap();
+
+        let spooled: SpooledEvent = serde_json::from_slice(&fs::read(path).unwrap()).unwrap();
+        assert_eq!(spooled.event, event("1"));
+        assert_eq!(spooled.endpoints, vec![String::from("https://a"), String::from("https://b")]);
+    }
+
+    #[tokio::test]
+    async fn flush_when_endpoint_accepts_then_spool_file_removed(){
+        let mut server = mockito::Server::new_async().await;
+        let mock = server.mock("POST", "/events").with_status(202).expect(1).create_async().await;
+        let dir = spool_dir("accepts");
+        let url = format!("{}/events", server.url());
+        let path = store(&dir, &event("1"), vec![url.clone()]).unwrap();
+
+        let remaining = flush(&dir, &[endpoint(url, ContentMode::Structured)], throttle()).await.unwrap();
+
+        assert_eq!(remaining, 0);
+        assert!(!path.exists());
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn flush_when_one_endpoint_fails_then_only_failed_endpoint_kept(){
+        let mut server = mockito::Server::new_async().await;
+        server.mock("POST", "/ok").with_status(202).create_async().await;
+        server.mock("POST", "/down").with_status(503).create_async().await;
+        let dir = spool_dir("fails");
+        let ok = format!("{}/ok", server.url());
+        let down = format!("{}/down", server.url());
+        let path = store(&dir, &event("1"), vec![ok.clone(), down.clone()]).unwrap();
+
+        let remaining = flush(&dir, &[endpoint(ok, ContentMode::Structured), endpoint(down.clone(), ContentMode::Structured)], throttle()).await.unwrap();
+
+        assert_eq!(remaining, 1);
+        let spooled: SpooledEvent = serde_json::from_slice(&fs::read(path).unwrap()).unwrap();
+        assert_eq!(spooled.endpoints, vec![down]);
+    }
+}
@@ -0,0 +1,98 @@
+use std::error::Error;
+use cloudevents::{Data, Event};
+
+/// Replaces `{{name}}` placeholders with values from the event.
+///
+/// `name` is a CloudEvent attribute or extension such as `id`, `type` or `subject`,
+/// or `data.` followed by a dotted path into the JSON payload, eg. `{{data.subject.content.environment.id}}`.
+pub fn render(template: &str, event: &Event) -> Result<String, Box<dyn Error + Send + Sync + 'static>>
+{
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let end = rest[start..].find("}}").ok_or_else(|| format!("unterminated `{{{{` in `{template}`"))?;
+        rendered.push_str(&rest[..start]);
+        rendered.push_str(&lookup(rest[start + 2..start + end].trim(), event)?);
+        rest = &rest[start + end + 2..];
+    }
+    rendered.push_str(rest);
+    Ok(rendered)
+}
+
+/// Renders a value that is either a template, or the bare name of a placeholder such as `subject` for `{{subject}}`.
+#[cfg(any(feature = "amqp", feature = "kafka"))]
+pub fn render_field(name_or_template: &str, event: &Event) -> Result<String, Box<dyn Error + Send + Sync + 'static>>
+{
+    if name_or_template.contains("{{") {
+        render(name_or_template, event)
+    } else {
+        lookup(name_or_template, event)
+    }
+}
+
+fn lookup(name: &str, event: &Event) -> Result<String, Box<dyn Error + Send + Sync + 'static>>
+{
+    if let Some(path) = name.strip_prefix("data.") {
+        return lookup_data(path, event).ok_or_else(|| format!("`{name}` not found in the event data").into());
+    }
+    event.iter()
+        .find(|(attribute, _)| *attribute == name)
+        .map(|(_, value)| value.to_string())
+        .ok_or_else(|| format!("`{name}` is not an attribute of the event").into())
+}
+
+fn lookup_data(path: &str, event: &Event) -> Option<String>
+{
+    let Some(Data::Json(json)) = event.data() else {
+        return None;
+    };
+    let value = path.split('.').try_fold(json, |value, key| value.get(key))?;
+    match value {
+        serde_json::Value::String(s) => Some(s.clone()),
+        serde_json::Value::Null => None,
+        other => Some(other.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn event() -> Event {
+        let mut event = crate::binding::test_event("1");
+        event.set_data("application/json", json!({"subject": {"content": {"environment": {"id": "local"}}}}));
+        event
+    }
+
+    #[test]
+    fn render_when_no_placeholders_then_unchanged(){
+        assert_eq!(render("static", &event()).unwrap(), "static")
+    }
+
+    #[test]
+    fn render_when_attributes_then_values(){
+        assert_eq!(render("{{type}}/{{ id }}/{{subject}}", &event()).unwrap(), "dev.cdevents.service.deployed.0.1.1/1/xyz")
+    }
+
+    #[test]
+    fn render_when_data_path_then_value(){
+        assert_eq!(render("env-{{data.subject.content.environment.id}}", &event()).unwrap(), "env-local")
+    }
+
+    #[cfg(any(feature = "amqp", feature = "kafka"))]
+    #[test]
+    fn render_field_when_bare_name_then_value(){
+        assert_eq!(render_field("subject", &event()).unwrap(), "xyz")
+    }
+
+    #[test]
+    fn render_when_unknown_name_then_error(){
+        assert!(render("{{nope}}", &event()).is_err())
+    }
+
+    #[test]
+    fn render_when_unterminated_placeholder_then_error(){
+        assert!(render("{{id", &event()).is_err())
+    }
+}
@@ -0,0 +1,91 @@
+use std::sync::Arc;
+use std::time::Duration;
+use clap::{arg, Arg, ArgMatches};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::{Interval, MissedTickBehavior};
+
+// ========= Concurrency and Rate Limits =========
+#[derive(Clone, Copy, Debug)]
+pub struct ThrottleArgs {
+    pub concurrency: usize,
+    pub max_rps: Option<u32>,
+}
+
+pub fn throttle_args() -> [Arg; 2] {
+    [
+        arg!(--concurrency <N> "How many events are sent at the same time")
+            .default_value("4")
+            .value_parser(clap::value_parser!(u32).range(1..)),
+        arg!(--"max-rps" <N> "The most events sent per second, to each endpoint")
+            .value_parser(clap::value_parser!(u32).range(1..)),
+    ]
+}
+
+pub fn throttle_parse(matches: &ArgMatches) -> ThrottleArgs {
+    let concurrency = *matches.get_one::<u32>("concurrency").unwrap() as usize;
+    let max_rps = matches.get_one::<u32>("max-rps").copied();
+    ThrottleArgs {
+        concurrency,
+        max_rps
+    }
+}
+
+/// Limits how many events are in flight, and how often one can be started.
+pub struct Throttle {
+    semaphore: Arc<Semaphore>,
+    interval: Option<Interval>,
+}
+
+impl Throttle {
+    pub fn new(args: ThrottleArgs) -> Throttle {
+        let interval = args.max_rps.map(|max_rps| {
+            let mut interval = tokio::time::interval(Duration::from_secs(1) / max_rps);
+            // A slow request must not be followed by a burst that catches up on the ticks it missed.
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            interval
+        });
+        Throttle {
+            semaphore: Arc::new(Semaphore::new(args.concurrency)),
+            interval
+        }
+    }
+
+    /// Waits until another event may be sent, returning the permit to hold while it is in flight.
+    pub async fn acquire(&mut self) -> OwnedSemaphorePermit {
+        let permit = self.semaphore.clone().acquire_owned().await.expect("the semaphore is never closed");
+        if let Some(interval) = &mut self.interval {
+            interval.tick().await;
+        }
+        permit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::time::Instant;
+
+    #[tokio::test(start_paused = true)]
+    async fn acquire_when_max_rps_then_spaced_by_interval(){
+        let mut throttle = Throttle::new(ThrottleArgs { concurrency: 4, max_rps: Some(10) });
+        let start = Instant::now();
+
+        for _ in 0..3 {
+            drop(throttle.acquire().await);
+        }
+
+        assert_eq!(start.elapsed(), Duration::from_millis(200));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn acquire_when_concurrency_reached_then_waits_for_permit(){
+        let mut throttle = Throttle::new(ThrottleArgs { concurrency: 1, max_rps: None });
+        let permit = throttle.acquire().await;
+
+        let waiting = tokio::time::timeout(Duration::from_secs(1), throttle.acquire()).await;
+        assert!(waiting.is_err());
+
+        drop(permit);
+        assert!(tokio::time::timeout(Duration::from_secs(1), throttle.acquire()).await.is_ok());
+    }
+}
@@ -0,0 +1,101 @@
+use std::error::Error;
+use std::path::Path;
+use std::process::Command;
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+
+/// Epoch values from this one on are taken as milliseconds, as in seconds they would be thousands of years away.
+const EPOCH_MILLIS_FROM: i64 = 100_000_000_000;
+
+/// Parses an RFC 3339 date, Unix epoch seconds or milliseconds, or `git:<rev>` for the committer date of a commit, eg. `git:HEAD`.
+pub fn parse_timestamp(s: &str) -> Result<OffsetDateTime, Box<dyn Error + Send + Sync + 'static>>
+{
+    if let Some(rev) = s.strip_prefix("git:") {
+        return git_commit_time(Path::new("."), rev);
+    }
+    if let Ok(epoch) = s.parse::<i64>() {
+        return if epoch.abs() >= EPOCH_MILLIS_FROM {
+            Ok(OffsetDateTime::from_unix_timestamp_nanos(epoch as i128 * 1_000_000)?)
+        } else {
+            Ok(OffsetDateTime::from_unix_timestamp(epoch)?)
+        };
+    }
+    OffsetDateTime::parse(s, &Rfc3339)
+        .map_err(|e| format!("`{s}` is not an RFC 3339 date, epoch seconds or milliseconds, or git:<rev>: {e}").into())
+}
+
+fn git_commit_time(dir: &Path, rev: &str) -> Result<OffsetDateTime, Box<dyn Error + Send + Sync + 'static>>
+{
+    // A revision starting with `-` would be taken by git as an option, such as `--output=<file>`.
+    if rev.is_empty() || rev.starts_with('-') {
+        return Err(format!("`{rev}` is not a git revision").into());
+    }
+    let output = Command::new("git")
+        .arg("-C").arg(dir)
+        .args(["log", "-1", "--format=%ct", rev, "--"])
+        .output()
+        .map_err(|e| format!("could not run git: {e}"))?;
+    if !output.status.success() {
+        return Err(format!("git could not find commit `{rev}`: {}", String::from_utf8_lossy(&output.stderr).trim()).into());
+    }
+    let epoch = String::from_utf8(output.stdout)?.trim().parse::<i64>()?;
+    Ok(OffsetDateTime::from_unix_timestamp(epoch)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::macros::datetime;
+
+    #[test]
+    fn parse_timestamp_when_git_rev_is_an_option_then_error(){
+        let path = std::env::temp_dir().join(format!("cdevent-test-{}-git-output", std::process::id()));
+
+        let result = parse_timestamp(&format!("git:--output={}", path.display()));
+
+        assert!(result.is_err());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn parse_timestamp_when_rfc3339_then_date(){
+        assert_eq!(parse_timestamp("2024-09-10T04:55:25+02:00").unwrap(), datetime!(2024-09-10 02:55:25 UTC))
+    }
+
+    #[test]
+    fn parse_timestamp_when_epoch_seconds_then_date(){
+        assert_eq!(parse_timestamp("1725944125").unwrap(), datetime!(2024-09-10 04:55:25 UTC))
+    }
+
+    #[test]
+    fn parse_timestamp_when_epoch_millis_then_date(){
+        assert_eq!(parse_timestamp("1725944125250").unwrap(), datetime!(2024-09-10 04:55:25.25 UTC))
+    }
+
+    #[test]
+    fn parse_timestamp_when_invalid_then_error(){
+        assert!(parse_timestamp("yesterday").is_err())
+    }
+
+    #[test]
+    fn git_commit_time_when_commit_then_committer_date(){
+        let dir = std::env::temp_dir().join(format!("cdevent-test-{}-git", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .arg("-C").arg(&dir)
+                .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+                .args(args)
+                .env("GIT_COMMITTER_DATE", "1725944125 +0000")
+                .status()
+                .unwrap();
+            assert!(status.success());
+        };
+        git(&["init", "-q"]);
+        git(&["commit", "-q", "--allow-empty", "-m", "deploy"]);
+
+        assert_eq!(git_commit_time(&dir, "HEAD").unwrap(), datetime!(2024-09-10 04:55:25 UTC));
+        assert!(git_commit_time(&dir, "nope").is_err());
+    }
+}
@@ -0,0 +1,116 @@
+use std::error::Error;
+use hyper::header::HOST;
+use hyperlocal::UnixClientExt;
+use reqwest::StatusCode;
+
+/// Splits a `unix:///run/events.sock:/path` endpoint URL into the socket path and the request path, which defaults to `/`.
+pub fn socket_and_path(url: &str) -> (&str, &str) {
+    let rest = url.trim_start_matches("unix://");
+    match rest.split_once(':') {
+        Some((socket, path)) if path.starts_with('/') => (socket, path),
+        Some((socket, _)) => (socket, "/"),
+        None => (rest, "/"),
+    }
+}
+
+/// The URL the request is built with, so it goes through the same content mode, header, authentication and signing steps as HTTP.
+pub fn request_url(url: &str) -> String {
+    let (_, path) = socket_and_path(url);
+    format!("http://localhost{path}")
+}
+
+/// Sends a request built for [`request_url`] over the endpoint's Unix domain socket.
+pub async fn execute(url: &str, request: reqwest::Request) -> Result<StatusCode, Box<dyn Error + Send + Sync + 'static>> {
+    let (socket, path) = socket_and_path(url);
+    let body = match request.body() {
+        Some(body) => body.as_bytes().ok_or("cannot send a streaming request body over a Unix socket")?.to_vec(),
+        None => Vec::new(),
+    };
+    let mut builder = hyper::Request::builder()
+        .method(request.method().clone())
+        .uri(hyperlocal::Uri::new(socket, path))
+        .header(HOST, "localhost");
+    for (name, value) in request.headers() {
+        builder = builder.header(name, value);
+    }
+    let response = hyper::Client::unix()
+        .request(builder.body(hyper::Body::from(body))?)
+        .await
+        .map_err(|e| format!("{socket}: {e}"))?;
+    Ok(response.status())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binding::test_endpoint;
+    use cloudevents::{Event, EventBuilder, EventBuilderV10};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::UnixListener;
+    use crate::endpoint::{ContentMode, Endpoint};
+    use crate::http;
+
+    /// Accepts one connection, answers `202 Accepted` and returns the raw request.
+    async fn serve_once(listener: UnixListener) -> String {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut request = Vec::new();
+        let mut buf = [0; 4096];
+        while !is_complete(&request) {
+            let read = stream.read(&mut buf).await.unwrap();
+            if read == 0 {
+                break;
+            }
+            request.extend_from_slice(&buf[..read]);
+        }
+        stream.write_all(b"HTTP/1.1 202 Accepted\r\ncontent-length: 0\r\n\r\n").await.unwrap();
+        String::from_utf8(request).unwrap()
+    }
+
+    fn is_complete(request: &[u8]) -> bool {
+        let request = String::from_utf8_lossy(request);
+        let Some((head, body)) = request.split_once("\r\n\r\n") else {
+            return false;
+        };
+        let length = head.lines()
+            .find_map(|line| line.to_ascii_lowercase().strip_prefix("content-length:").map(|value| value.trim().to_string()))
+            .and_then(|value| value.parse::<usize>().ok())
+            .unwrap_or(0);
+        body.len() >= length
+    }
+
+    #[test]
+    fn socket_and_path_when_path_then_split(){
+        assert_eq!(socket_and_path("unix:///run/events.sock:/v1/events"), ("/run/events.sock", "/v1/events"))
+    }
+
+    #[test]
+    fn socket_and_path_when_no_path_then_root(){
+        assert_eq!(socket_and_path("unix:///run/events.sock"), ("/run/events.sock", "/"))
+    }
+
+    #[tokio::test]
+    async fn send_when_unix_endpoint_then_posted_over_socket(){
+        let socket = std::env::temp_dir().join(format!("cdevent-test-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&socket);
+        let listener = UnixListener::bind(&socket).unwrap();
+        let server = tokio::spawn(serve_once(listener));
+        let endpoint = Endpoint {
+            headers: vec![(String::from("X-Team"), String::from("team1"))],
+            ..test_endpoint(format!("unix://{}:/events", socket.display()), ContentMode::Structured)
+        };
+        let event: Event = EventBuilderV10::new()
+            .id("1")
+            .source("zsh")
+            .ty("dev.cdevents.service.deployed.0.1.1")
+            .build()
+            .unwrap();
+
+        let status = http::send(&reqwest::Client::new(), &endpoint, &[event]).await;
+
+        assert_eq!(status.unwrap(), "202 Accepted");
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /events HTTP/1.1\r\n"));
+        assert!(request.to_ascii_lowercase().contains("x-team: team1\r\n"));
+        assert!(request.contains(r#""id":"1""#));
+    }
+}